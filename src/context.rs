@@ -0,0 +1,25 @@
+//! Execution contexts, which hold everything needed to suspend a line of
+//! execution and continue it later
+
+use crate::frame::*;
+use crate::value::Value;
+
+#[derive(Debug, Clone, Default)]
+pub struct Context {
+	pub instruction_pointer: usize,
+	pub registers: Vec<Value>,
+	pub stack: Vec<Value>,
+	pub call_stack: Vec<CallFrame>,
+	pub handlers: Vec<ExceptionHandler>,
+}
+
+impl Context {
+	/// Creates a context that starts executing at `address`, with `registers` as its initial registers
+	pub fn new(address: usize, registers: Vec<Value>) -> Self {
+		Self {
+			instruction_pointer: address,
+			registers,
+			..Default::default()
+		}
+	}
+}
@@ -0,0 +1,40 @@
+//! Implementation of coroutines, which run on their own registers and stack
+//! and can suspend themselves with `Instruction::Yield`
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::context::Context;
+use crate::register::Register;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoroutineStatus {
+	Suspended,
+	Running,
+	Finished,
+}
+
+#[derive(Debug)]
+pub struct Coroutine {
+	/// The coroutine's own context while it is suspended,
+	/// and the context of whoever resumed it while it is running
+	pub context: Context,
+	pub status: CoroutineStatus,
+}
+
+impl Coroutine {
+	pub fn new(context: Context) -> Self {
+		Self {
+			context,
+			status: CoroutineStatus::Suspended,
+		}
+	}
+}
+
+/// A coroutine that is currently running, and the register of its resumer
+/// that receives the next yielded value
+#[derive(Debug, Clone)]
+pub struct ActiveCoroutine {
+	pub coroutine: Rc<RefCell<Coroutine>>,
+	pub register: Register,
+}
@@ -31,49 +31,184 @@ impl std::fmt::Display for Color {
 #[derive(Debug, Clone)]
 pub enum Error {
     ExpectedType(String, Register),
-    ArrayIndexOutOfBounds(usize),
+    ArrayIndexOutOfBounds(i64, usize),
+    IndexOutOfBounds(i64, usize),
+    KeyNotFound(String, Register),
+    InvalidByte(i64),
+    InvalidUtf8(Register),
+    InvalidCodepoint(i64),
+    TypeMismatch(String, String),
+    FloatOutOfRange(f64),
+    InvalidNumber(String, String),
+    InvalidRadix(u32),
+    NotOrdered(String),
+    UnorderedNaN,
     DivisionByZero,
+    IntegerOverflow,
+    InvalidShift(i64),
     LabelNotDefined(Label),
+    LabelAlreadyDefined(Label),
+    StructNotDefined(String),
+    StructAlreadyDefined(String),
+    FieldNotDefined(String, String),
+    InvalidJumpTarget(i64),
+    InvalidTailCall,
+    MissingReturn,
+    UncaughtException(String),
+    ArityMismatch(u16, usize),
+    CoroutineNotSuspended,
+    YieldOutsideCoroutine,
+    Deadlock(Vec<usize>),
 	StackUnderflow,
 	StackOverflow,
 }
 
 impl std::error::Error for Error {}
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let err = format!("[{} error {}]", Color::Red, Color::Reset);
-
+impl Error {
+    /// The error message without the colored prefix, as seen by guest exception handlers
+    pub fn message(&self) -> String {
         match self {
             Self::ExpectedType(expected_type, register) => {
-                write!(
-                    f,
-                    "{err} expected type '{expected_type}' in register '{register}'"
-                )
+                format!("expected type '{expected_type}' in register '{register}'")
+            }
+
+            Self::ArrayIndexOutOfBounds(index, length) => {
+                format!("index '{index}' is out of bounds for array of length {length}")
+            }
+
+            Self::IndexOutOfBounds(index, length) => {
+                format!("index '{index}' is out of bounds for length {length}")
+            }
+
+            Self::InvalidByte(value) => {
+                format!("'{value}' is not a valid byte")
+            }
+
+            Self::InvalidUtf8(register) => {
+                format!("bytes in register '{register}' are not valid UTF-8")
+            }
+
+            Self::InvalidCodepoint(value) => {
+                format!("'{value}' is not a valid Unicode code point")
+            }
+
+            Self::FloatOutOfRange(value) => {
+                format!("float '{value}' is out of range for an integer")
             }
 
-            Self::ArrayIndexOutOfBounds(index) => {
-                write!(f, "{err} index '{index}' is out of bounds for array")
+            Self::InvalidNumber(string, number_type) => {
+                format!("cannot parse '{string}' as {number_type}")
+            }
+
+            Self::InvalidRadix(radix) => {
+                format!("radix '{radix}' is not between 2 and 36")
+            }
+
+            Self::TypeMismatch(a, b) => {
+                format!("cannot compare a value of type '{a}' with a value of type '{b}'")
+            }
+
+            Self::NotOrdered(value_type) => {
+                format!("values of type '{value_type}' have no ordering")
+            }
+
+            Self::UnorderedNaN => {
+                String::from("NaN cannot be ordered")
+            }
+
+            Self::KeyNotFound(key, register) => {
+                format!("key '{key}' not found in map in register '{register}'")
             }
 
             Self::DivisionByZero => {
-                write!(f, "{err} division by zero")
+                String::from("division by zero")
+            }
+
+            Self::IntegerOverflow => {
+                String::from("integer overflow")
+            }
+
+            Self::InvalidShift(amount) => {
+                format!("shift amount '{amount}' is not between 0 and 63")
             }
 
             Self::LabelNotDefined(label) => {
-                write!(f, "{err} label '{}' is not defined", label.0)
+                format!("label '{}' is not defined", label.name)
+            }
+
+            Self::LabelAlreadyDefined(label) => {
+                format!("label '{}' is already defined", label.name)
+            }
+
+            Self::StructNotDefined(name) => {
+                format!("struct '{name}' is not defined")
+            }
+
+            Self::StructAlreadyDefined(name) => {
+                format!("struct '{name}' is already defined")
+            }
+
+            Self::FieldNotDefined(struct_name, field_name) => {
+                format!("struct '{struct_name}' has no field '{field_name}'")
+            }
+
+            Self::InvalidJumpTarget(address) => {
+                format!("jump target '{address}' is outside of the program")
+            }
+
+            Self::InvalidTailCall => {
+                String::from("tail call outside of a function")
+            }
+
+            Self::MissingReturn => {
+                String::from("function ran off the end of the program without returning")
+            }
+
+            Self::UncaughtException(value) => {
+                format!("uncaught exception '{value}'")
+            }
+
+            Self::ArityMismatch(expected, given) => {
+                format!("function expects {expected} arguments but {given} were given")
+            }
+
+            Self::CoroutineNotSuspended => {
+                String::from("coroutine is running or has already finished")
+            }
+
+            Self::YieldOutsideCoroutine => {
+                String::from("yield outside of a coroutine")
+            }
+
+            Self::Deadlock(threads) => {
+                let threads = threads
+                    .iter()
+                    .map(|id| id.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                format!("deadlock, every thread is blocked (threads {threads})")
             }
 
 			Self::StackUnderflow => {
-				write!(f, "{err} stack underflow")
+				String::from("stack underflow")
 			}
 
 			Self::StackOverflow => {
-				write!(f, "{err} stack overflow")
+				String::from("stack overflow")
 			}
         }
     }
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let err = format!("[{} error {}]", Color::Red, Color::Reset);
+
+        write!(f, "{err} {}", self.message())
+    }
+}
+
 /// The result type used in the VM
 pub type Result<T> = std::result::Result<T, Error>;
@@ -1,4 +1,8 @@
-//! Implementation of stack frames for stack tracing in the occurrence of an error
+//! Implementation of stack frames for stack tracing in the occurrence of an error,
+//! and of the call frames used by guest function calls
+
+use crate::register::Register;
+use crate::value::Value;
 
 #[derive(Debug, Clone)]
 pub struct StackFrame {
@@ -6,3 +10,27 @@ pub struct StackFrame {
 	pub function_name: String,
 	pub line: u32,
 }
+
+/// A guest function call that has not returned yet
+#[derive(Debug, Clone)]
+pub struct CallFrame {
+	/// The address execution continues at once the callee returns
+	pub return_address: usize,
+	/// The register file of the caller, restored once the callee returns
+	pub registers: Vec<Value>,
+	/// The caller's register that receives the callee's return value
+	pub return_register: Register,
+}
+
+/// An exception handler installed by `Instruction::Try`
+#[derive(Debug, Clone)]
+pub struct ExceptionHandler {
+	/// The address execution continues at when an exception is caught
+	pub catch_address: usize,
+	/// The register that receives the exception value
+	pub register: Register,
+	/// The depth of the call stack when the handler was installed
+	pub call_depth: usize,
+	/// The length of the value stack when the handler was installed
+	pub stack_length: usize,
+}
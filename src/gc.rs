@@ -0,0 +1,374 @@
+//! A mark-and-sweep garbage collector for heap values.
+//!
+//! Arrays, maps, structs, coroutines and channels are reference counted, so they are
+//! freed as soon as nothing refers to them. Values that refer to each other in a cycle
+//! are never freed that way, so the collector marks everything reachable from the
+//! roots of the VM and clears every tracked value that was not reached, which breaks
+//! the cycles and lets reference counting free them.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::rc::{Rc, Weak};
+
+use crate::context::Context;
+use crate::coroutine::{Coroutine, CoroutineStatus};
+use crate::value::{MapKey, Value};
+
+/// The default minimum number of allocations after which a collection runs
+pub const DEFAULT_GC_THRESHOLD: usize = 1000;
+
+/// A heap value tracked by the collector
+#[derive(Debug)]
+enum HeapObject {
+	Array(Weak<RefCell<Vec<Value>>>),
+	Map(Weak<RefCell<BTreeMap<MapKey, Value>>>),
+	Coroutine(Weak<RefCell<Coroutine>>),
+	Channel(Weak<RefCell<VecDeque<Value>>>),
+}
+
+impl HeapObject {
+	fn is_alive(&self) -> bool {
+		match self {
+			Self::Array(array) => array.strong_count() > 0,
+			Self::Map(map) => map.strong_count() > 0,
+			Self::Coroutine(coroutine) => coroutine.strong_count() > 0,
+			Self::Channel(channel) => channel.strong_count() > 0,
+		}
+	}
+
+	/// Clears the contents of the value, returning whether it was still alive
+	fn clear(&self) -> bool {
+		match self {
+			Self::Array(array) => take_contents(array),
+			Self::Map(map) => take_contents(map),
+			Self::Channel(channel) => take_contents(channel),
+			Self::Coroutine(coroutine) => match coroutine.upgrade() {
+				Some(coroutine) => {
+					let context = {
+						let mut coroutine = coroutine.borrow_mut();
+						coroutine.status = CoroutineStatus::Finished;
+						std::mem::take(&mut coroutine.context)
+					};
+
+					drop(context);
+					true
+				}
+				None => false,
+			},
+		}
+	}
+}
+
+/// Moves the contents out of a value before dropping them, so that they are
+/// never dropped while the value itself is borrowed
+fn take_contents<T: Default>(value: &Weak<RefCell<T>>) -> bool {
+	match value.upgrade() {
+		Some(value) => {
+			let contents = std::mem::take(&mut *value.borrow_mut());
+
+			drop(contents);
+			true
+		}
+		None => false,
+	}
+}
+
+/// The address of a heap value, which identifies it in the collector
+fn value_address(value: &Value) -> Option<usize> {
+	match value {
+		Value::Array(array) => Some(Rc::as_ptr(array) as usize),
+		Value::Map(map) => Some(Rc::as_ptr(map) as usize),
+		Value::Struct(_, values) => Some(Rc::as_ptr(values) as usize),
+		Value::Coroutine(coroutine) => Some(Rc::as_ptr(coroutine) as usize),
+		Value::Channel(channel) => Some(Rc::as_ptr(channel) as usize),
+		_ => None,
+	}
+}
+
+/// Statistics about the collector, exposed through `BoltVM::gc_stats`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GcStats {
+	/// The number of collections that have run
+	pub collections: usize,
+	/// The number of unreachable values freed by collections
+	pub objects_freed: usize,
+	/// The number of tracked values that were alive after the last collection
+	pub live_objects: usize,
+	/// The number of values allocated since the last collection
+	pub allocations: usize,
+}
+
+#[derive(Debug)]
+pub struct Heap {
+	/// Every tracked value, by address
+	objects: HashMap<usize, HeapObject>,
+	/// The number of allocations after which the next collection runs. It grows with
+	/// the number of live values, so a large live heap is not marked over and over
+	pub threshold: usize,
+	/// The lowest value the threshold is lowered to after a collection
+	pub min_threshold: usize,
+	pub stats: GcStats,
+}
+
+impl Default for Heap {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Heap {
+	pub fn new() -> Self {
+		Self {
+			objects: HashMap::new(),
+			threshold: DEFAULT_GC_THRESHOLD,
+			min_threshold: DEFAULT_GC_THRESHOLD,
+			stats: GcStats::default(),
+		}
+	}
+
+	/// Starts tracking a newly created value, and any heap values nested in it
+	/// that are not tracked yet
+	pub fn track(&mut self, value: &Value) {
+		// the weak reference keeps a tracked value's memory allocated, so its
+		// address cannot be reused by a new value until the entry is removed
+		if let Some(address) = value_address(value) {
+			if self.objects.contains_key(&address) {
+				return;
+			}
+		}
+
+		let (address, object) = match value {
+			Value::Array(array) => {
+				array.borrow().iter().for_each(|value| self.track(value));
+				(Rc::as_ptr(array) as usize, HeapObject::Array(Rc::downgrade(array)))
+			}
+			Value::Map(map) => {
+				map.borrow().values().for_each(|value| self.track(value));
+				(Rc::as_ptr(map) as usize, HeapObject::Map(Rc::downgrade(map)))
+			}
+			Value::Struct(_, values) => {
+				values.borrow().iter().for_each(|value| self.track(value));
+				(Rc::as_ptr(values) as usize, HeapObject::Array(Rc::downgrade(values)))
+			}
+			Value::Coroutine(coroutine) => (
+				Rc::as_ptr(coroutine) as usize,
+				HeapObject::Coroutine(Rc::downgrade(coroutine)),
+			),
+			Value::Channel(channel) => (
+				Rc::as_ptr(channel) as usize,
+				HeapObject::Channel(Rc::downgrade(channel)),
+			),
+			_ => return,
+		};
+
+		self.objects.insert(address, object);
+		self.stats.allocations += 1;
+	}
+
+	#[inline]
+	pub fn should_collect(&self) -> bool {
+		self.stats.allocations >= self.threshold
+	}
+
+	/// Frees every tracked value that the marker did not reach
+	pub fn sweep(&mut self, marker: &Marker) {
+		let mut freed = 0;
+
+		self.objects.retain(|address, object| {
+			if marker.marked.contains(address) {
+				return object.is_alive();
+			}
+
+			if object.clear() {
+				freed += 1;
+			}
+
+			false
+		});
+
+		self.stats.collections += 1;
+		self.stats.objects_freed += freed;
+		self.stats.live_objects = self.objects.len();
+		self.stats.allocations = 0;
+
+		// the next collection runs once the heap has grown to twice its live size
+		self.threshold = self.min_threshold.max(self.stats.live_objects);
+	}
+}
+
+/// Marks the heap values reachable from a set of roots
+#[derive(Debug, Default)]
+pub struct Marker {
+	marked: HashSet<usize>,
+	/// Values whose contents still have to be marked. Using a worklist
+	/// instead of recursion keeps deeply nested values from overflowing the stack
+	pending: Vec<Value>,
+}
+
+impl Marker {
+	pub fn mark_values<'a>(&mut self, values: impl IntoIterator<Item = &'a Value>) {
+		for value in values {
+			self.mark(value);
+		}
+
+		while let Some(value) = self.pending.pop() {
+			self.mark_contents(&value);
+		}
+	}
+
+	pub fn mark_context(&mut self, context: &Context) {
+		self.mark_values(&context.registers);
+		self.mark_values(&context.stack);
+
+		for frame in context.call_stack.iter() {
+			self.mark_values(&frame.registers);
+		}
+	}
+
+	/// Marks a value itself, queueing its contents if it was not marked yet
+	fn mark(&mut self, value: &Value) {
+		if let Value::Closure(_, _, captures) = value {
+			captures.iter().for_each(|value| self.mark(value));
+			return;
+		}
+
+		let Some(address) = value_address(value) else {
+			return;
+		};
+
+		if self.marked.insert(address) {
+			self.pending.push(value.clone());
+		}
+	}
+
+	fn mark_contents(&mut self, value: &Value) {
+		match value {
+			Value::Array(values) | Value::Struct(_, values) => {
+				values.borrow().iter().for_each(|value| self.mark(value));
+			}
+			Value::Map(map) => map.borrow().values().for_each(|value| self.mark(value)),
+			Value::Channel(channel) => channel.borrow().iter().for_each(|value| self.mark(value)),
+			Value::Coroutine(coroutine) => {
+				let coroutine = coroutine.borrow();
+				let context = &coroutine.context;
+
+				context.registers.iter().for_each(|value| self.mark(value));
+				context.stack.iter().for_each(|value| self.mark(value));
+
+				for frame in context.call_stack.iter() {
+					frame.registers.iter().for_each(|value| self.mark(value));
+				}
+			}
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::RefCell;
+	use std::rc::Rc;
+
+	use super::{Heap, Marker};
+	use crate::instruction::Instruction::{self, *};
+	use crate::register::Register;
+	use crate::types;
+	use crate::value::Value;
+	use crate::vm::BoltVM;
+
+	fn run(program: Vec<Instruction>) -> BoltVM {
+		let mut vm = BoltVM::new();
+
+		vm.execute(program).expect("program should run");
+		vm
+	}
+
+	fn label(name: &str) -> types::Label {
+		types::Label::new(name)
+	}
+
+	#[test]
+	fn self_cycle_is_freed() {
+		let vm = run(vec![
+			DefineStruct(String::from("Node"), vec![String::from("next")]),
+			NewStruct(Register(0), String::from("Node")),
+			SetField(Register(0), types::Field::new("Node", "next"), Register(0)),
+			LoadInt(Register(0), 0),
+			Gc,
+		]);
+
+		let stats = vm.gc_stats();
+		assert_eq!(stats.collections, 1);
+		assert_eq!(stats.objects_freed, 1);
+		assert_eq!(stats.live_objects, 0);
+	}
+
+	#[test]
+	fn closure_capture_in_suspended_coroutine_survives() {
+		let vm = run(vec![
+			CreateArray(Register(0)),
+			ArrayAdd(Register(0), Value::Int(42)),
+			MakeClosure(Register(2), label("get"), 0, vec![Register(0)]),
+			CreateCoroutine(Register(3), label("body"), vec![Register(2)]),
+			Resume(Register(4), Register(3)),
+			// the array is now only reachable through the suspended coroutine
+			LoadInt(Register(0), 0),
+			LoadInt(Register(2), 0),
+			Gc,
+			Resume(Register(4), Register(3)),
+			Halt,
+			Label(label("body")),
+			Yield(Register(1)),
+			CallValue(Register(0), vec![], Register(1)),
+			Return(Register(1)),
+			Label(label("get")),
+			GetArrayElemPtr(Register(1), Register(0), 0),
+			Return(Register(1)),
+		]);
+
+		assert_eq!(vm.gc_stats().objects_freed, 0);
+		assert!(matches!(vm.registers[4], Value::Int(42)));
+	}
+
+	#[test]
+	fn value_queued_in_channel_survives() {
+		let vm = run(vec![
+			ChannelNew(Register(0)),
+			CreateArray(Register(1)),
+			ArrayAdd(Register(1), Value::Int(7)),
+			Send(Register(0), Register(1)),
+			// the array is now only reachable through the channel
+			LoadInt(Register(1), 0),
+			Gc,
+			Recv(Register(2), Register(0)),
+			GetArrayElemPtr(Register(3), Register(2), 0),
+		]);
+
+		assert_eq!(vm.gc_stats().objects_freed, 0);
+		assert!(matches!(vm.registers[3], Value::Int(7)));
+	}
+
+	#[test]
+	fn threshold_grows_with_live_objects() {
+		let mut heap = Heap::new();
+		heap.min_threshold = 2;
+
+		let values: Vec<Value> = (0..10)
+			.map(|_| Value::Array(Rc::new(RefCell::new(Vec::new()))))
+			.collect();
+		values.iter().for_each(|value| heap.track(value));
+
+		let mut marker = Marker::default();
+		marker.mark_values(&values);
+		heap.sweep(&marker);
+
+		assert_eq!(heap.stats.live_objects, 10);
+		assert_eq!(heap.threshold, 10);
+
+		drop(values);
+		heap.sweep(&Marker::default());
+
+		assert_eq!(heap.stats.live_objects, 0);
+		assert_eq!(heap.threshold, 2);
+	}
+}
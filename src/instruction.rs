@@ -1,4 +1,5 @@
 use crate::register::Register;
+use crate::types::*;
 use crate::value::*;
 
 /// Define the instructions for the virtual machine
@@ -6,45 +7,99 @@ use crate::value::*;
 pub enum Instruction {
     // Regular instructions
     /// Push an integer into a register
-    LoadInt(Register, i32),
+    LoadInt(Register, i64),
+    /// Push an unsigned integer into a register. Unsigned integers are always 64 bits wide,
+    /// there are no narrower integer types
+    LoadUInt(Register, u64),
     /// Push a float into a register
-    LoadFlt(Register, f32),
+    LoadFlt(Register, f64),
     /// Push a string into a register
     LoadStr(Register, String),
     /// Push a boolean into a register
     LoadBool(Register, bool),
     /// Add two integers and store the result in a register       
     AddInt(Register, Register, Register),
+    /// Add two unsigned integers and store the result in a register
+    AddUInt(Register, Register, Register),
     /// Add two floats and store the result in a register
     AddFlt(Register, Register, Register),
     /// Subtract two integers and store the result in a register
     SubInt(Register, Register, Register),
+    /// Subtract two unsigned integers and store the result in a register
+    SubUInt(Register, Register, Register),
     /// Subtract two floats and store the result in a register
     SubFlt(Register, Register, Register),
     /// Multiply two integers and store the result in a register
     MulInt(Register, Register, Register),
+    /// Multiply two unsigned integers and store the result in a register
+    MulUInt(Register, Register, Register),
     /// Multiply two floats and store the result in a register
     MulFlt(Register, Register, Register),
     /// Divide two integers and store the result in a register
     DivInt(Register, Register, Register),
+    /// Divide two unsigned integers and store the result in a register
+    DivUInt(Register, Register, Register),
     /// Divide two floats and store the result in a register
     DivFlt(Register, Register, Register),
+    /// Store the remainder of dividing two integers in a register. The remainder has the sign of the dividend
+    ModInt(Register, Register, Register),
+    /// Negate the integer in the second register
+    NegInt(Register, Register),
+    /// Negate the float in the second register
+    NegFlt(Register, Register),
+    /// Perform a bitwise AND on two integers and store the result in a register
+    BitAnd(Register, Register, Register),
+    /// Perform a bitwise OR on two integers and store the result in a register
+    BitOr(Register, Register, Register),
+    /// Perform a bitwise XOR on two integers and store the result in a register
+    BitXor(Register, Register, Register),
+    /// Invert the bits of the integer in the second register
+    BitNot(Register, Register),
+    /// Shift the integer in the second register left by the number of bits in the third register
+    Shl(Register, Register, Register),
+    /// Shift the integer in the second register right by the number of bits in the third register,
+    /// copying the sign bit into the vacated bits
+    Shr(Register, Register, Register),
+    /// Shift the integer in the second register right by the number of bits in the third register,
+    /// filling the vacated bits with zeros
+    ShrLogical(Register, Register, Register),
     /// Concatenate two strings and store the result in a register
     ConcatStrings(Register, Register, Register),
     /// Perform a logical AND operation and store the result in a register
     AndBool(Register, Register, Register),
     /// Perform a logical OR operation and store the result in a register
     OrBool(Register, Register, Register),
+    /// Perform a logical NOT operation on the boolean in the second register
+    NotBool(Register, Register),
+    /// Perform a logical XOR operation and store the result in a register
+    XorBool(Register, Register, Register),
     /// Check if the first integer is less than the second and store the result in a register
     LtInt(Register, Register, Register),
+    /// Check if the first unsigned integer is less than the second and store the result in a register
+    LtUInt(Register, Register, Register),
     /// Check if the first integer is greater than the second and store the result in a register
     GtInt(Register, Register, Register),
+    /// Check if the first unsigned integer is greater than the second and store the result in a register
+    GtUInt(Register, Register, Register),
     /// Check if the first float is less than the second and store the result in a register
     LtFlt(Register, Register, Register),
     /// Check if the first float is greater than the second and store the result in a register
     GtFlt(Register, Register, Register),
     /// Check if two booleans are equal and store the result in a register
     EqBool(Register, Register, Register),
+    /// Check if two values of the same type are structurally equal and store the result in a register.
+    /// Null can be compared with any value
+    Eq(Register, Register, Register),
+    /// Check if two values of the same type are not structurally equal and store the result in a register.
+    /// Null can be compared with any value
+    Ne(Register, Register, Register),
+    /// Check if the first value is less than or equal to the second and store the result in a register
+    Le(Register, Register, Register),
+    /// Check if the first value is greater than or equal to the second and store the result in a register
+    Ge(Register, Register, Register),
+    /// Compare two values and store -1, 0 or 1 in a register, depending on whether the first
+    /// value is less than, equal to or greater than the second
+    Cmp(Register, Register, Register),
     /// Print the value of a register to standard output
     Print(ValueOrRegister),
     /// Creates an array with the given register
@@ -61,6 +116,315 @@ pub enum Instruction {
 	Pop(Register),
 	/// Copy the value of one register to the other
 	CopyReg(Register, Register),
+
+    // Array instructions. Indexes are integers stored in registers
+    /// Append the value of the second register to the array in the first register
+    ArrayPush(Register, Register),
+    /// Get the element at the index in the third register of the array in the second register
+    ArrayGet(Register, Register, Register),
+    /// Set the element at the index in the second register of the array in the first register
+    /// to the value of the third register
+    ArraySet(Register, Register, Register),
+    /// Remove the last element of the array in the second register and store it in the first register
+    ArrayPop(Register, Register),
+    /// Insert the value of the third register at the index in the second register of the array in
+    /// the first register, shifting the following elements to the right
+    ArrayInsert(Register, Register, Register),
+    /// Remove the element at the index in the third register of the array in the second register,
+    /// shifting the following elements to the left, and store it in the first register
+    ArrayRemove(Register, Register, Register),
+    /// Store the elements between the start index in the third register and the end index in the
+    /// fourth register of the array in the second register, as a new array
+    ArraySlice(Register, Register, Register, Register),
+    /// Store the elements of the array in the second register followed by the elements of the array
+    /// in the third register, as a new array
+    ArrayConcat(Register, Register, Register),
+
+    // Map instructions
+    /// Create an empty map and store it in the register
+    CreateMap(Register),
+    /// Set the key in the second register to the value in the third register, in the map in the first register
+    MapSet(Register, Register, Register),
+    /// Get the value for the key in the third register from the map in the second register
+    MapGet(Register, Register, Register),
+    /// Check if the map in the second register contains the key in the third register
+    MapHas(Register, Register, Register),
+    /// Remove the key in the third register from the map in the second register, and store its value
+    MapRemove(Register, Register, Register),
+    /// Store an array of the keys of the map in the second register, in ascending order
+    MapKeys(Register, Register),
+    /// Store the number of entries of the map in the second register
+    MapLength(Register, Register),
+
+    // Wrapping and saturating integer instructions. The regular integer
+    // instructions raise an error on overflow instead
+    /// Add two integers, wrapping around on overflow, and store the result in a register
+    AddIntWrap(Register, Register, Register),
+    /// Add two integers, saturating at the numeric bounds, and store the result in a register
+    AddIntSat(Register, Register, Register),
+    /// Subtract two integers, wrapping around on overflow, and store the result in a register
+    SubIntWrap(Register, Register, Register),
+    /// Subtract two integers, saturating at the numeric bounds, and store the result in a register
+    SubIntSat(Register, Register, Register),
+    /// Multiply two integers, wrapping around on overflow, and store the result in a register
+    MulIntWrap(Register, Register, Register),
+    /// Multiply two integers, saturating at the numeric bounds, and store the result in a register
+    MulIntSat(Register, Register, Register),
+    /// Divide two integers, wrapping around on overflow, and store the result in a register
+    DivIntWrap(Register, Register, Register),
+    /// Divide two integers, saturating at the numeric bounds, and store the result in a register
+    DivIntSat(Register, Register, Register),
+    /// Store the remainder of dividing two integers in a register, wrapping around on overflow.
+    /// `i64::MIN` modulo -1 is 0
+    ModIntWrap(Register, Register, Register),
+    /// Negate the integer in the second register, wrapping around on overflow
+    NegIntWrap(Register, Register),
+    /// Negate the integer in the second register, saturating at the numeric bounds
+    NegIntSat(Register, Register),
+    /// Add two unsigned integers, wrapping around on overflow, and store the result in a register
+    AddUIntWrap(Register, Register, Register),
+    /// Add two unsigned integers, saturating at the numeric bounds, and store the result in a register
+    AddUIntSat(Register, Register, Register),
+    /// Subtract two unsigned integers, wrapping around on overflow, and store the result in a register
+    SubUIntWrap(Register, Register, Register),
+    /// Subtract two unsigned integers, saturating at the numeric bounds, and store the result in a register
+    SubUIntSat(Register, Register, Register),
+    /// Multiply two unsigned integers, wrapping around on overflow, and store the result in a register
+    MulUIntWrap(Register, Register, Register),
+    /// Multiply two unsigned integers, saturating at the numeric bounds, and store the result in a register
+    MulUIntSat(Register, Register, Register),
+
+    // Conversion instructions
+    /// Convert the integer in the second register to a float
+    IntToFloat(Register, Register),
+    /// Convert the float in the second register to an integer, rounding it with the given mode.
+    /// Raises an error if the result does not fit in an integer
+    FloatToInt(Register, Register, RoundingMode),
+    /// Convert the integer in the second register to an unsigned integer.
+    /// Raises an error if it is negative
+    IntToUInt(Register, Register),
+    /// Convert the unsigned integer in the second register to an integer.
+    /// Raises an error if it is greater than the largest integer
+    UIntToInt(Register, Register),
+    /// Store the value of the second register as a string, formatted like `Print` does
+    ToString(Register, Register),
+    /// Parse the string in the second register as an integer in the given radix, or base 10
+    ParseInt(Register, Register, Option<u32>),
+    /// Parse the string in the second register as a float
+    ParseFloat(Register, Register),
+
+    // Type instructions
+    /// Store the name of the type of the value in the second register as a string, e.g. "int" or "array"
+    TypeOf(Register, Register),
+    /// Check if the value in the second register has the given type
+    IsType(Register, Register, ValueType),
+    /// Raise an error if the value in the register does not have the given type
+    AssertType(Register, ValueType),
+
+    // Math instructions on floats
+    /// Store the square root of the float in the second register
+    Sqrt(Register, Register),
+    /// Raise the float in the second register to the power of the float in the third register
+    Pow(Register, Register, Register),
+    /// Store e raised to the power of the float in the second register
+    Exp(Register, Register),
+    /// Store the natural logarithm of the float in the second register
+    Ln(Register, Register),
+    /// Store the sine of the float in the second register, in radians
+    Sin(Register, Register),
+    /// Store the cosine of the float in the second register, in radians
+    Cos(Register, Register),
+    /// Store the tangent of the float in the second register, in radians
+    Tan(Register, Register),
+    /// Store the angle in radians of the point whose y coordinate is in the second register
+    /// and x coordinate is in the third register
+    Atan2(Register, Register, Register),
+    /// Round the float in the second register towards negative infinity
+    Floor(Register, Register),
+    /// Round the float in the second register towards positive infinity
+    Ceil(Register, Register),
+    /// Round the float in the second register to the nearest integer, and half-way cases away from zero
+    Round(Register, Register),
+    /// Store the absolute value of the float in the second register
+    Abs(Register, Register),
+    /// Store the smaller of two floats. If one of them is NaN, the other one is stored
+    Min(Register, Register, Register),
+    /// Store the larger of two floats. If one of them is NaN, the other one is stored
+    Max(Register, Register, Register),
+    /// Check if the float in the second register is NaN
+    IsNaN(Register, Register),
+
+    // String instructions. Lengths and indexes count chars, not bytes
+    /// Store the number of chars of the string in the second register
+    StrLen(Register, Register),
+    /// Store the chars between the start index in the third register and the end index in the
+    /// fourth register of the string in the second register
+    Substring(Register, Register, Register, Register),
+    /// Store the index of the first occurrence of the string in the third register in the string
+    /// in the second register, or -1 if it does not occur
+    StrIndexOf(Register, Register, Register),
+    /// Split the string in the second register at every occurrence of the separator in the third
+    /// register, and store the parts as an array. An empty separator splits into single chars
+    StrSplit(Register, Register, Register),
+    /// Replace every occurrence of the string in the third register by the string in the fourth
+    /// register, in the string in the second register
+    StrReplace(Register, Register, Register, Register),
+    /// Remove leading and trailing whitespace from the string in the second register
+    StrTrim(Register, Register),
+    /// Convert the string in the second register to uppercase
+    StrUpper(Register, Register),
+    /// Convert the string in the second register to lowercase
+    StrLower(Register, Register),
+    /// Check if the string in the second register starts with the string in the third register
+    StrStartsWith(Register, Register, Register),
+    /// Store the char at the index in the third register of the string in the second register, as a string
+    CharAt(Register, Register, Register),
+    /// Store the Unicode code point of the char at the index in the third register of the string
+    /// in the second register, as an integer
+    CodepointAt(Register, Register, Register),
+    /// Store the char with the Unicode code point in the second register, as a string
+    FromCodepoint(Register, Register),
+
+    // Byte buffer instructions
+    /// Create an empty byte buffer and store it in the register
+    CreateBytes(Register),
+    /// Get the byte at the index in the third register of the buffer in the second register, as an integer
+    BytesGet(Register, Register, Register),
+    /// Store the bytes between the start index in the third register and the end index in the
+    /// fourth register of the buffer in the second register, as a new buffer
+    BytesSlice(Register, Register, Register, Register),
+    /// Append a byte, given as an integer, or another buffer in the second register to the buffer in the first register
+    BytesAppend(Register, Register),
+    /// Store the length of the buffer in the second register
+    BytesLength(Register, Register),
+    /// Store the UTF-8 encoding of the string in the second register as a buffer
+    StrToBytes(Register, Register),
+    /// Decode the buffer in the second register as UTF-8 and store the string
+    BytesToStr(Register, Register),
+
+    // Struct instructions
+    /// Declare a struct type with the given name and fields. Struct types are resolved before execution
+    DefineStruct(String, Vec<String>),
+    /// Create an instance of the named struct type, with every field set to null
+    NewStruct(Register, String),
+    /// Get a field of the struct in the second register and store it in the first register
+    GetField(Register, Register, Field),
+    /// Set a field of the struct in the first register to the value in the second register
+    SetField(Register, Field, Register),
+
+    // Control flow instructions
+    /// Marks a position in the program that can be jumped to. Labels are resolved before execution
+    Label(Label),
+    /// Jump to the given label
+    Jump(Label),
+    /// Jump to the given address
+    JumpAddr(Address),
+    /// Jump to the given label if the value in the register is truthy
+    JumpIfTrue(Register, Label),
+    /// Jump to the given label if the value in the register is not truthy
+    JumpIfFalse(Register, Label),
+    /// Jump to the address stored as an integer in the register
+    JumpReg(Register),
+    /// Jump to the label at the index stored as an integer in the register, or to the
+    /// default label if the index is out of range
+    Switch(Register, Vec<Label>, Label),
+    /// Call the function at the given label. The callee gets its own registers, with the
+    /// arguments copied into r0, r1, ..., and its return value is stored in the last register
+    Call(Label, Vec<Register>, Register),
+    /// Call the function or closure stored in the first register. Arguments and the return
+    /// value are passed like with `Call`, and captured values follow the arguments
+    CallValue(Register, Vec<Register>, Register),
+    /// Call the function at the given label, reusing the current call frame. The callee
+    /// returns directly to the current function's caller
+    TailCall(Label, Vec<Register>),
+    /// Return the value of a register from the current function to its caller
+    Return(Register),
+    /// Push a function with the given label and number of parameters into a register
+    LoadFunc(Register, Label, u16),
+    /// Push a closure into a register, capturing the current values of the given registers
+    MakeClosure(Register, Label, u16, Vec<Register>),
+
+    // Coroutine instructions
+    /// Create a suspended coroutine that runs the function at the given label, with the
+    /// arguments copied into its registers like with `Call`
+    CreateCoroutine(Register, Label, Vec<Register>),
+    /// Resume the coroutine in the second register, and store the value it yields or
+    /// returns in the first register
+    Resume(Register, Register),
+    /// Suspend the current coroutine, handing the value of the register to its resumer
+    Yield(Register),
+
+    // Thread instructions
+    /// Spawn a thread that runs the function at the given label, with the arguments copied
+    /// into its registers like with `Call`, and store a handle to it in the register
+    Spawn(Register, Label, Vec<Register>),
+    /// Wait for the thread in the second register to finish, and store its result in the first
+    Join(Register, Register),
+    /// Create a channel and store it in the register
+    ChannelNew(Register),
+    /// Send the value of the second register on the channel in the first register
+    Send(Register, Register),
+    /// Receive a value from the channel in the second register and store it in the first.
+    /// The thread waits until a value is available
+    Recv(Register, Register),
+
+    // Exception handling instructions
+    /// Install an exception handler. Exceptions raised until the matching `EndTry` store their
+    /// value in the register and continue execution at the label
+    Try(Label, Register),
+    /// Remove the innermost exception handler
+    EndTry,
+    /// Raise the value in the register as an exception
+    Throw(Register),
+    /// Run a garbage collection
+    Gc,
     /// Stop execution
     Halt,
 }
+
+impl Instruction {
+    /// Returns the labels this instruction refers to, so they can be checked before execution
+    pub fn label_references(&self) -> Vec<&Label> {
+        match self {
+            Self::Jump(label)
+            | Self::JumpIfTrue(_, label)
+            | Self::JumpIfFalse(_, label)
+            | Self::Call(label, _, _)
+            | Self::TailCall(label, _)
+            | Self::Try(label, _)
+            | Self::LoadFunc(_, label, _)
+            | Self::MakeClosure(_, label, _, _)
+            | Self::CreateCoroutine(_, label, _)
+            | Self::Spawn(_, label, _) => {
+                vec![label]
+            }
+            Self::Switch(_, labels, default) => {
+                labels.iter().chain(std::iter::once(default)).collect()
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Returns the labels this instruction jumps or calls through, so their addresses
+    /// can be resolved before execution
+    pub fn label_references_mut(&mut self) -> Vec<&mut Label> {
+        match self {
+            Self::Jump(label)
+            | Self::JumpIfTrue(_, label)
+            | Self::JumpIfFalse(_, label)
+            | Self::Call(label, _, _)
+            | Self::TailCall(label, _)
+            | Self::Try(label, _)
+            | Self::LoadFunc(_, label, _)
+            | Self::MakeClosure(_, label, _, _)
+            | Self::CreateCoroutine(_, label, _)
+            | Self::Spawn(_, label, _) => {
+                vec![label]
+            }
+            Self::Switch(_, labels, default) => {
+                labels.iter_mut().chain(std::iter::once(default)).collect()
+            }
+            _ => vec![],
+        }
+    }
+}
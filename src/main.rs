@@ -1,7 +1,11 @@
+pub mod context;
+pub mod coroutine;
 pub mod error;
 pub mod frame;
+pub mod gc;
 pub mod instruction;
 pub mod register;
+pub mod thread;
 pub mod types;
 pub mod value;
 pub mod vm;
@@ -1,4 +1,4 @@
-/// The registers of the virtual machine. There are 65535 registers in total.
+/// The registers of the virtual machine. Every call frame has its own set of 65535 registers.
 #[derive(Debug, Copy, Clone)]
 pub struct Register(pub u16);
 
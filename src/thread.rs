@@ -0,0 +1,49 @@
+//! Implementation of green threads, which are scheduled by the VM itself
+//! and all run on the same OS thread
+
+use crate::context::Context;
+use crate::coroutine::ActiveCoroutine;
+use crate::value::{Channel, Value};
+
+/// The id of the thread that runs the program passed to `BoltVM::execute`
+pub const MAIN_THREAD: usize = 0;
+
+#[derive(Debug, Clone)]
+pub enum ThreadState {
+	Runnable,
+	/// Waiting for the thread with the given id to finish
+	Joining(usize),
+	/// Waiting for a value to be sent on the channel
+	Receiving(Channel),
+	/// Finished with the given result
+	Finished(Value),
+}
+
+#[derive(Debug, Clone)]
+pub struct Thread {
+	/// The thread's context while it is not running
+	pub context: Context,
+	/// The thread's running coroutines while it is not running
+	pub active_coroutines: Vec<ActiveCoroutine>,
+	pub state: ThreadState,
+}
+
+impl Thread {
+	pub fn new(context: Context) -> Self {
+		Self {
+			context,
+			active_coroutines: vec![],
+			state: ThreadState::Runnable,
+		}
+	}
+
+	#[inline]
+	pub fn is_runnable(&self) -> bool {
+		matches!(self.state, ThreadState::Runnable)
+	}
+
+	#[inline]
+	pub fn is_blocked(&self) -> bool {
+		matches!(self.state, ThreadState::Joining(_) | ThreadState::Receiving(_))
+	}
+}
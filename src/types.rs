@@ -1,8 +1,85 @@
-//! Just some types to represent `Address`es and 
-//! `Label`s, instead of just using `usize` and `String`
+//! Just some types to represent `Address`es, `Label`s, struct
+//! fields and rounding modes, instead of just using `usize` and `String`
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Label(pub String);
+/// A position in the program, referred to by name. Its address is resolved before execution
+#[derive(Debug, Clone)]
+pub struct Label {
+	pub name: String,
+	pub address: Option<usize>,
+}
+
+impl Label {
+	pub fn new(name: &str) -> Self {
+		Self {
+			name: name.to_owned(),
+			address: None,
+		}
+	}
+}
+
+/// Labels are identified by their name alone, whether they are resolved or not
+impl PartialEq for Label {
+	fn eq(&self, other: &Self) -> bool {
+		self.name == other.name
+	}
+}
+
+impl Eq for Label {}
+
+impl std::hash::Hash for Label {
+	fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+		self.name.hash(state);
+	}
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Address(pub usize);
+
+/// A declared struct type: its name and its fields, in order
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StructType {
+	pub name: String,
+	pub fields: Vec<String>,
+}
+
+/// A field of a struct type. Its index is resolved before execution
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Field {
+	pub struct_name: String,
+	pub field_name: String,
+	pub index: Option<usize>,
+}
+
+impl Field {
+	pub fn new(struct_name: &str, field_name: &str) -> Self {
+		Self {
+			struct_name: struct_name.to_owned(),
+			field_name: field_name.to_owned(),
+			index: None,
+		}
+	}
+}
+
+/// How `FloatToInt` turns a float with a fractional part into an integer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RoundingMode {
+	/// Round towards zero
+	Truncate,
+	/// Round to the nearest integer, and half-way cases away from zero
+	Round,
+	/// Round towards negative infinity
+	Floor,
+	/// Round towards positive infinity
+	Ceil,
+}
+
+impl RoundingMode {
+	pub fn apply(self, value: f64) -> f64 {
+		match self {
+			Self::Truncate => value.trunc(),
+			Self::Round => value.round(),
+			Self::Floor => value.floor(),
+			Self::Ceil => value.ceil(),
+		}
+	}
+}
@@ -1,47 +1,101 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::rc::Rc;
+
+use crate::coroutine::Coroutine;
+use crate::error::{Error, Result};
 use crate::register::Register;
+use crate::types::{Label, StructType};
+
+/// An array shared by every register that refers to it, so that copying a
+/// register is cheap and mutations are visible through every copy
+pub type Array = Rc<RefCell<Vec<Value>>>;
+
+/// A map shared by every register that refers to it, like `Array`
+pub type Map = Rc<RefCell<BTreeMap<MapKey, Value>>>;
+
+/// A queue of values shared by every register and thread that refers to it
+pub type Channel = Rc<RefCell<VecDeque<Value>>>;
 
 /// All the possible value types
 #[derive(Debug, Clone)]
 pub enum Value {
-    Int(i32),
-    Float(f32),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
     String(String),
     Bool(bool),
-    Array(Vec<Value>),
+    Array(Array),
+    Map(Map),
+    Bytes(Vec<u8>),
+    /// An instance of a struct type, with one value per field. The fields are shared like an `Array`
+    Struct(Rc<StructType>, Array),
+    /// A function label and the number of parameters it takes
+    Function(Label, u16),
+    /// A function label, its number of parameters and the values captured at creation
+    Closure(Label, u16, Vec<Value>),
+    /// A coroutine, shared by every register that refers to it
+    Coroutine(Rc<RefCell<Coroutine>>),
+    /// A handle to the thread with the given id
+    Thread(usize),
+    Channel(Channel),
     Null,
 }
 
 impl Value {
 	#[inline]
-    pub fn is_int(&self) -> bool {
-        match *self {
-            Value::Int(_) => true,
-            _ => false,
+    pub fn new_array(values: Vec<Value>) -> Self {
+        Value::Array(Rc::new(RefCell::new(values)))
+    }
+
+	/// Clones the value, copying arrays, maps and structs instead of sharing them
+    pub fn deep_clone(&self) -> Self {
+        match self {
+            Value::Array(values) => {
+                Value::new_array(values.borrow().iter().map(Value::deep_clone).collect())
+            }
+            Value::Map(map) => {
+                let map = map
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.deep_clone()))
+                    .collect();
+
+                Value::Map(Rc::new(RefCell::new(map)))
+            }
+            Value::Struct(struct_type, values) => {
+                let values = values.borrow().iter().map(Value::deep_clone).collect();
+
+                Value::Struct(struct_type.clone(), Rc::new(RefCell::new(values)))
+            }
+            _ => self.clone(),
         }
     }
 
+	#[inline]
+    pub fn is_int(&self) -> bool {
+        matches!(*self, Value::Int(_))
+    }
+
+	#[inline]
+    pub fn is_uint(&self) -> bool {
+        matches!(*self, Value::UInt(_))
+    }
+
 	#[inline]
     pub fn is_float(&self) -> bool {
-        match *self {
-            Value::Float(_) => true,
-            _ => false,
-        }
+        matches!(*self, Value::Float(_))
     }
 
 	#[inline]
     pub fn is_string(&self) -> bool {
-        match *self {
-            Value::String(_) => true,
-            _ => false,
-        }
+        matches!(*self, Value::String(_))
     }
 
 	#[inline]
     pub fn is_bool(&self) -> bool {
-        match *self {
-            Value::Bool(_) => true,
-            _ => false,
-        }
+        matches!(*self, Value::Bool(_))
     }
 
 	#[inline]
@@ -54,18 +108,44 @@ impl Value {
 
 	#[inline]
     pub fn is_list(&self) -> bool {
-        match *self {
-            Value::Array(_) => true,
-            _ => false,
+        matches!(*self, Value::Array(_))
+    }
+
+	#[inline]
+    pub fn is_map(&self) -> bool {
+        matches!(*self, Value::Map(_))
+    }
+
+	#[inline]
+    pub fn is_bytes(&self) -> bool {
+        matches!(*self, Value::Bytes(_))
+    }
+
+	#[inline]
+    pub fn is_struct(&self) -> bool {
+        matches!(*self, Value::Struct(..))
+    }
+
+	/// Returns the map key for this value, or `None` if it cannot be used as one
+	#[inline]
+    pub fn as_map_key(&self) -> Option<MapKey> {
+        match self {
+            Value::Int(value) => Some(MapKey::Int(*value)),
+            Value::UInt(value) => Some(MapKey::UInt(*value)),
+            Value::String(value) => Some(MapKey::String(value.clone())),
+            Value::Bool(value) => Some(MapKey::Bool(*value)),
+            _ => None,
         }
     }
 
+	#[inline]
+    pub fn is_callable(&self) -> bool {
+        matches!(*self, Value::Function(..) | Value::Closure(..))
+    }
+
 	#[inline]
     pub fn is_null(&self) -> bool {
-        match *self {
-            Value::Null => true,
-            _ => false,
-        }
+        matches!(*self, Value::Null)
     }
 
 	#[inline]
@@ -76,28 +156,331 @@ impl Value {
         }
     }
 
+	/// Returns the address an integer value points to, or `None` if it is not
+	/// an integer or is negative
 	#[inline]
-    pub fn as_jump_target(&self) -> usize {
+    pub fn as_jump_target(&self) -> Option<usize> {
         match *self {
-            Value::Int(value) => value as usize,
-            _ => panic!("expected integer value as jump target"),
+            Value::Int(value) => usize::try_from(value).ok(),
+            _ => None,
+        }
+    }
+
+	/// The type of the value
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            Value::Int(_) => ValueType::Int,
+            Value::UInt(_) => ValueType::UInt,
+            Value::Float(_) => ValueType::Float,
+            Value::String(_) => ValueType::String,
+            Value::Bool(_) => ValueType::Bool,
+            Value::Array(_) => ValueType::Array,
+            Value::Map(_) => ValueType::Map,
+            Value::Bytes(_) => ValueType::Bytes,
+            Value::Struct(..) => ValueType::Struct,
+            Value::Function(..) => ValueType::Function,
+            Value::Closure(..) => ValueType::Closure,
+            Value::Coroutine(_) => ValueType::Coroutine,
+            Value::Thread(_) => ValueType::Thread,
+            Value::Channel(_) => ValueType::Channel,
+            Value::Null => ValueType::Null,
+        }
+    }
+
+	/// The name of the value's type, as used in error messages
+	#[inline]
+    pub fn type_name(&self) -> &'static str {
+        self.value_type().name()
+    }
+
+	/// Compares two values structurally: arrays, maps and structs are equal when
+	/// their contents are, coroutines and channels only when they are the same
+	/// object, NaN is never equal to anything and values of different types are
+	/// never equal
+    pub fn equals(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a == b,
+            (Value::UInt(a), Value::UInt(b)) => a == b,
+            (Value::Float(a), Value::Float(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Bool(a), Value::Bool(b)) => a == b,
+            (Value::Bytes(a), Value::Bytes(b)) => a == b,
+            (Value::Array(a), Value::Array(b)) => {
+                Rc::ptr_eq(a, b)
+                    || compare_once(a, b, true, || values_equal(&a.borrow(), &b.borrow()))
+            }
+            (Value::Map(a), Value::Map(b)) => {
+                Rc::ptr_eq(a, b)
+                    || compare_once(a, b, true, || {
+                        let (a, b) = (a.borrow(), b.borrow());
+
+                        a.len() == b.len()
+                            && a.iter().zip(b.iter()).all(|((key_a, value_a), (key_b, value_b))| {
+                                key_a == key_b && value_a.equals(value_b)
+                            })
+                    })
+            }
+            (Value::Struct(type_a, a), Value::Struct(type_b, b)) => {
+                type_a.name == type_b.name
+                    && (Rc::ptr_eq(a, b)
+                        || compare_once(a, b, true, || values_equal(&a.borrow(), &b.borrow())))
+            }
+            (Value::Function(label_a, arity_a), Value::Function(label_b, arity_b)) => {
+                label_a == label_b && arity_a == arity_b
+            }
+            (Value::Closure(label_a, arity_a, a), Value::Closure(label_b, arity_b, b)) => {
+                label_a == label_b && arity_a == arity_b && values_equal(a, b)
+            }
+            (Value::Coroutine(a), Value::Coroutine(b)) => Rc::ptr_eq(a, b),
+            (Value::Thread(a), Value::Thread(b)) => a == b,
+            (Value::Channel(a), Value::Channel(b)) => Rc::ptr_eq(a, b),
+            (Value::Null, Value::Null) => true,
+            _ => false,
         }
     }
+
+	/// Orders two values of the same type. Numbers, strings, booleans and bytes
+	/// are ordered naturally and arrays lexicographically, `None` is returned
+	/// when a NaN is involved
+    pub fn compare(&self, other: &Value) -> Result<Option<Ordering>> {
+        let ordering = match (self, other) {
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::UInt(a), Value::UInt(b)) => a.cmp(b),
+            (Value::Float(a), Value::Float(b)) => return Ok(a.partial_cmp(b)),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.cmp(b),
+            (Value::Array(a), Value::Array(b)) => {
+                return compare_once(a, b, Ok(Some(Ordering::Equal)), || {
+                    let (a, b) = (a.borrow(), b.borrow());
+
+                    for (a, b) in a.iter().zip(b.iter()) {
+                        match a.compare(b)? {
+                            Some(Ordering::Equal) => continue,
+                            ordering => return Ok(ordering),
+                        }
+                    }
+
+                    Ok(Some(a.len().cmp(&b.len())))
+                })
+            }
+            (Value::Null, Value::Null) => Ordering::Equal,
+            _ if self.type_name() != other.type_name() => {
+                return Err(Error::TypeMismatch(
+                    self.type_name().to_string(),
+                    other.type_name().to_string(),
+                ))
+            }
+            _ => return Err(Error::NotOrdered(self.type_name().to_string())),
+        };
+
+        Ok(Some(ordering))
+    }
+}
+
+thread_local! {
+    /// The pairs of heap values that are currently being compared
+    static COMPARING: RefCell<Vec<(usize, usize)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Compares two heap values, or returns `cycle` if the same pair is already being
+/// compared further up, so that values which contain themselves can still be compared.
+/// A pair that is reached again is treated as equal, since any difference will be
+/// found elsewhere
+fn compare_once<T, R>(a: &Rc<T>, b: &Rc<T>, cycle: R, compare: impl FnOnce() -> R) -> R {
+    let pair = (Rc::as_ptr(a) as usize, Rc::as_ptr(b) as usize);
+    let comparing = COMPARING.with(|comparing| {
+        let mut comparing = comparing.borrow_mut();
+        let comparing_already = comparing.contains(&pair);
+
+        if !comparing_already {
+            comparing.push(pair);
+        }
+
+        comparing_already
+    });
+
+    if comparing {
+        return cycle;
+    }
+
+    let result = compare();
+    COMPARING.with(|comparing| comparing.borrow_mut().pop());
+    result
+}
+
+/// Compares two lists of values element by element
+fn values_equal(a: &[Value], b: &[Value]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| a.equals(b))
 }
 
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             Value::Int(val) => write!(f, "{}", val),
+            Value::UInt(val) => write!(f, "{}", val),
             Value::Float(val) => write!(f, "{}", val),
             Value::String(val) => write!(f, "{}", val),
             Value::Bool(val) => write!(f, "{}", val),
-            Value::Array(val) => write!(f, "{:?}", val),
+            Value::Array(val) => print_once(f, Rc::as_ptr(val) as usize, "[...]", |f| {
+                let values = val
+                    .borrow()
+                    .iter()
+                    .map(|value| value.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "[{values}]")
+            }),
+            Value::Map(val) => print_once(f, Rc::as_ptr(val) as usize, "{...}", |f| {
+                let entries = val
+                    .borrow()
+                    .iter()
+                    .map(|(key, value)| format!("{key}: {value}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                write!(f, "{{{entries}}}")
+            }),
+            Value::Bytes(val) => write!(f, "b\"{}\"", val.escape_ascii()),
+            Value::Struct(struct_type, values) => {
+                let cycle = format!("{} {{ ... }}", struct_type.name);
+
+                print_once(f, Rc::as_ptr(values) as usize, &cycle, |f| {
+                    let fields = struct_type
+                        .fields
+                        .iter()
+                        .zip(values.borrow().iter())
+                        .map(|(field, value)| format!("{field}: {value}"))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+
+                    write!(f, "{} {{ {fields} }}", struct_type.name)
+                })
+            }
+            Value::Function(label, arity) => write!(f, "<function {}/{}>", label.name, arity),
+            Value::Closure(label, arity, _) => write!(f, "<closure {}/{}>", label.name, arity),
+            Value::Coroutine(_) => write!(f, "<coroutine>"),
+            Value::Thread(id) => write!(f, "<thread {}>", id),
+            Value::Channel(_) => write!(f, "<channel>"),
             Value::Null => write!(f, "null"),
         }
     }
 }
 
+thread_local! {
+    /// The addresses of the heap values that are currently being printed
+    static PRINTING: RefCell<Vec<usize>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Prints a heap value, or `cycle` if the value is already being printed
+/// further up, so that values which contain themselves can still be printed
+fn print_once(
+    f: &mut std::fmt::Formatter,
+    address: usize,
+    cycle: &str,
+    print: impl FnOnce(&mut std::fmt::Formatter) -> std::fmt::Result,
+) -> std::fmt::Result {
+    let printing = PRINTING.with(|printing| {
+        let mut printing = printing.borrow_mut();
+        let printing_already = printing.contains(&address);
+
+        if !printing_already {
+            printing.push(address);
+        }
+
+        printing_already
+    });
+
+    if printing {
+        return write!(f, "{cycle}");
+    }
+
+    let result = print(f);
+    PRINTING.with(|printing| printing.borrow_mut().pop());
+    result
+}
+
+/// The types a `Value` can have, as tested by `IsType` and `AssertType`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    Int,
+    UInt,
+    Float,
+    String,
+    Bool,
+    Array,
+    Map,
+    Bytes,
+    Struct,
+    Function,
+    Closure,
+    Coroutine,
+    Thread,
+    Channel,
+    Null,
+}
+
+impl ValueType {
+    /// The name of the type, which is the tag `TypeOf` stores
+    pub fn name(self) -> &'static str {
+        match self {
+            ValueType::Int => "int",
+            ValueType::UInt => "uint",
+            ValueType::Float => "float",
+            ValueType::String => "string",
+            ValueType::Bool => "bool",
+            ValueType::Array => "array",
+            ValueType::Map => "map",
+            ValueType::Bytes => "bytes",
+            ValueType::Struct => "struct",
+            ValueType::Function => "function",
+            ValueType::Closure => "closure",
+            ValueType::Coroutine => "coroutine",
+            ValueType::Thread => "thread",
+            ValueType::Channel => "channel",
+            ValueType::Null => "null",
+        }
+    }
+}
+
+impl std::fmt::Display for ValueType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// The values that can be used as keys of a `Value::Map`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MapKey {
+    Int(i64),
+    UInt(u64),
+    String(String),
+    Bool(bool),
+}
+
+impl From<MapKey> for Value {
+    fn from(key: MapKey) -> Self {
+        match key {
+            MapKey::Int(value) => Value::Int(value),
+            MapKey::UInt(value) => Value::UInt(value),
+            MapKey::String(value) => Value::String(value),
+            MapKey::Bool(value) => Value::Bool(value),
+        }
+    }
+}
+
+impl std::fmt::Display for MapKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            MapKey::Int(val) => write!(f, "{}", val),
+            MapKey::UInt(val) => write!(f, "{}", val),
+            MapKey::String(val) => write!(f, "{:?}", val),
+            MapKey::Bool(val) => write!(f, "{}", val),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ValueOrRegister {
     Value(String),
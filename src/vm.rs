@@ -1,522 +1,3104 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::rc::Rc;
 use std::vec;
 
+use crate::context::Context;
+use crate::coroutine::*;
 use crate::error::*;
 use crate::frame::*;
+use crate::gc::*;
 use crate::instruction::Instruction;
 use crate::register::Register;
-use crate::value::{Value, ValueOrRegister};
+use crate::thread::*;
+use crate::types::*;
+use crate::value::{Array, Channel, Map, MapKey, Value, ValueOrRegister};
 
 use backtrace::Backtrace;
 
+/// The default maximum number of nested guest calls
+pub const DEFAULT_MAX_CALL_DEPTH: usize = 1024;
+
+/// The default number of instructions a thread runs before another thread gets to run
+pub const DEFAULT_TIME_SLICE: usize = 100;
+
 /// The virtual machine implementation
 #[derive(Debug)]
 pub struct BoltVM {
-    /// 65535 registers in total
+    /// The registers of the current call frame, 65535 in total at the top level
     pub registers: Vec<Value>,
     pub stack: Vec<Value>,
     pub frames: Vec<StackFrame>,
     pub instruction_pointer: usize,
+    /// The program currently being executed, shared so that instructions can be
+    /// executed without copying them
+    pub program: Rc<[Instruction]>,
+    /// The address of every label in the program
+    pub labels: HashMap<Label, usize>,
+    /// Every struct type declared by the program
+    pub structs: HashMap<String, Rc<StructType>>,
+    /// The number of instructions executed so far
+    pub executed_instructions: usize,
+    /// The guest calls that have not returned yet
+    pub call_stack: Vec<CallFrame>,
+    /// The number of nested guest calls after which `Error::StackOverflow` is raised
+    pub max_call_depth: usize,
+    /// The installed exception handlers, innermost last
+    pub handlers: Vec<ExceptionHandler>,
+    /// Set by `Instruction::Halt` to stop execution
+    pub halted: bool,
+    /// The coroutines that are currently running, innermost last
+    pub active_coroutines: Vec<ActiveCoroutine>,
+    /// Every thread of the program. The state of the running thread lives in the VM itself
+    pub threads: Vec<Thread>,
+    pub current_thread: usize,
+    /// The number of instructions a thread runs before it is preempted
+    pub time_slice: usize,
+    /// The number of instructions the current thread has run since it was scheduled
+    pub slice_instructions: usize,
+    /// The garbage collector's view of every heap value
+    pub heap: Heap,
+}
+
+impl Default for BoltVM {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl BoltVM {
-    pub fn new() -> Self {
-        // initialize every register to 0
-        let mut regs = Vec::with_capacity(65535);
-        for _ in 0..regs.capacity() {
-            regs.push(Value::Null);
-        }
+impl BoltVM {
+    pub fn new() -> Self {
+        // initialize every register to 0
+        let mut regs = Vec::with_capacity(65535);
+        for _ in 0..regs.capacity() {
+            regs.push(Value::Null);
+        }
+
+        Self {
+            registers: regs,
+            stack: vec![],
+            frames: vec![],
+            instruction_pointer: 0,
+            program: Rc::new([]),
+            labels: HashMap::new(),
+            structs: HashMap::new(),
+            executed_instructions: 0,
+            call_stack: vec![],
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            handlers: vec![],
+            halted: false,
+            active_coroutines: vec![],
+            threads: vec![],
+            current_thread: MAIN_THREAD,
+            time_slice: DEFAULT_TIME_SLICE,
+            slice_instructions: 0,
+            heap: Heap::new(),
+        }
+    }
+
+    fn format_stack_frame(&mut self) -> String {
+        let mut result = String::new();
+
+        for frame in self.frames.iter() {
+            if frame.file_name.starts_with("boltvm") {
+                result.push_str(&format!(
+                    "	in '{}' at '{}', line {}\n",
+                    frame.file_name, frame.function_name, frame.line
+                ));
+            }
+        }
+
+        result
+    }
+
+    pub fn handle_error(&mut self) {
+        let backtrace = Backtrace::new();
+        let frames = backtrace.frames();
+
+        let mut converted_frames = vec![];
+        for frame in frames {
+            if let Some(symbol) = frame.symbols().first() {
+                if let Some(function_name) = symbol.name() {
+                    if let Some(file) = symbol.filename() {
+                        if let Some(line) = symbol.lineno() {
+                            converted_frames.push(StackFrame {
+                                file_name: file.file_name().unwrap().to_str().unwrap().to_owned(),
+                                function_name: function_name.to_string(),
+                                line,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+
+        self.frames.extend(converted_frames);
+        let stack_trace = self.format_stack_frame();
+        if stack_trace.is_empty() {
+            println!("	no stacktrace");
+        } else {
+            println!("{stack_trace}");
+        }
+
+        self.frames.clear();
+    }
+
+    fn increment_ip(&mut self) {
+        self.instruction_pointer += 1;
+    }
+
+    /// Collects the address of every label in the program, makes sure that every
+    /// label an instruction refers to is actually defined, and resolves the labels
+    /// that instructions jump or call through into addresses
+    fn resolve_labels(program: &mut [Instruction]) -> Result<HashMap<Label, usize>> {
+        let mut labels = HashMap::new();
+
+        for (address, instruction) in program.iter().enumerate() {
+            if let Instruction::Label(label) = instruction {
+                if labels.insert(label.clone(), address).is_some() {
+                    return Err(Error::LabelAlreadyDefined(label.clone()));
+                }
+            }
+        }
+
+        for instruction in program.iter() {
+            for label in instruction.label_references() {
+                if !labels.contains_key(label) {
+                    return Err(Error::LabelNotDefined(label.clone()));
+                }
+            }
+        }
+
+        for instruction in program.iter_mut() {
+            for label in instruction.label_references_mut() {
+                label.address = labels.get(label).copied();
+            }
+        }
+
+        Ok(labels)
+    }
+
+    /// Makes sure that an address computed at runtime points inside the program
+    #[inline]
+    fn check_jump_target(&self, address: usize) -> Result<usize> {
+        if address < self.program.len() {
+            Ok(address)
+        } else {
+            Err(Error::InvalidJumpTarget(address as i64))
+        }
+    }
+
+    /// Gets the address of a label. Labels in instructions are resolved before
+    /// execution, but labels in values built by the host still have to be looked up
+    #[inline]
+    fn label_address(&self, label: &Label) -> Result<usize> {
+        if let Some(address) = label.address {
+            return Ok(address);
+        }
+
+        match self.labels.get(label) {
+            Some(address) => Ok(*address),
+            None => Err(Error::LabelNotDefined(label.clone())),
+        }
+    }
+
+    /// Collects every struct type declared in the program, and resolves the
+    /// fields that instructions refer to into indexes
+    fn resolve_structs(program: &mut [Instruction]) -> Result<HashMap<String, Rc<StructType>>> {
+        let mut structs = HashMap::new();
+
+        for instruction in program.iter() {
+            if let Instruction::DefineStruct(name, fields) = instruction {
+                let struct_type = Rc::new(StructType {
+                    name: name.clone(),
+                    fields: fields.clone(),
+                });
+
+                if structs.insert(name.clone(), struct_type).is_some() {
+                    return Err(Error::StructAlreadyDefined(name.clone()));
+                }
+            }
+        }
+
+        for instruction in program.iter_mut() {
+            match instruction {
+                Instruction::NewStruct(_, name) if !structs.contains_key(name) => {
+                    return Err(Error::StructNotDefined(name.clone()));
+                }
+
+                Instruction::GetField(_, _, field) | Instruction::SetField(_, field, _) => {
+                    let struct_type = match structs.get(&field.struct_name) {
+                        Some(struct_type) => struct_type,
+                        None => return Err(Error::StructNotDefined(field.struct_name.clone())),
+                    };
+
+                    field.index = struct_type.fields.iter().position(|name| *name == field.field_name);
+
+                    if field.index.is_none() {
+                        return Err(Error::FieldNotDefined(
+                            field.struct_name.clone(),
+                            field.field_name.clone(),
+                        ));
+                    }
+                }
+
+                _ => {}
+            }
+        }
+
+        Ok(structs)
+    }
+
+    pub fn execute(&mut self, mut program: Vec<Instruction>) -> Result<()> {
+        self.frames.push(StackFrame {
+            file_name: String::from("boltvm"),
+            function_name: String::from("execute()"),
+            line: 80,
+        });
+
+        // every instruction pushes its own frame, which is only kept if it fails
+        let frames_length = self.frames.len();
+
+        self.labels = Self::resolve_labels(&mut program)?;
+        self.structs = Self::resolve_structs(&mut program)?;
+        self.program = program.into();
+        self.instruction_pointer = 0;
+        self.call_stack.clear();
+        self.handlers.clear();
+        self.halted = false;
+        self.active_coroutines.clear();
+
+        self.threads = vec![Thread::new(Context::default())];
+        self.current_thread = MAIN_THREAD;
+        self.slice_instructions = 0;
+
+        let result = self.run(frames_length);
+
+        // whichever thread stopped the program, the main thread's context is left loaded
+        if self.current_thread != MAIN_THREAD {
+            self.swap_thread(self.current_thread);
+            self.swap_thread(MAIN_THREAD);
+            self.current_thread = MAIN_THREAD;
+        }
+
+        result
+    }
+
+    fn run(&mut self, frames_length: usize) -> Result<()> {
+        while !self.halted {
+            self.frames.truncate(frames_length);
+
+            if self.instruction_pointer >= self.program.len() {
+                if !self.call_stack.is_empty() {
+                    // a function has to return to its caller explicitly
+                    let error = Error::MissingReturn;
+                    self.throw(Value::String(error.message()), error)?;
+                } else if let Some(register) = self.leave_coroutine(CoroutineStatus::Finished) {
+                    // running off the end of a coroutine body finishes it, like returning null
+                    self.set_register(register, Value::Null);
+                } else if self.current_thread == MAIN_THREAD {
+                    break;
+                } else {
+                    // running off the end of the body of a spawned thread finishes it
+                    self.finish_thread(Value::Null)?;
+                }
+
+                continue;
+            }
+
+            let program = Rc::clone(&self.program);
+            let instruction = &program[self.instruction_pointer];
+            self.executed_instructions += 1;
+            self.slice_instructions += 1;
+
+            match self.execute_instruction(instruction) {
+                Ok(()) => {}
+                // no thread is left that could handle a deadlock
+                Err(error @ Error::Deadlock(_)) => return Err(error),
+                Err(error) => self.throw(Value::String(error.message()), error)?,
+            }
+
+            if self.slice_instructions >= self.time_slice {
+                self.switch_thread()?;
+            }
+
+            if self.heap.should_collect() {
+                self.collect_garbage();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts tracking a newly created heap value, so that the garbage collector can free it
+    #[inline]
+    fn allocate(&mut self, value: Value) -> Value {
+        self.heap.track(&value);
+        value
+    }
+
+    /// Frees every heap value that cannot be reached from the state of a thread or coroutine
+    pub fn collect_garbage(&mut self) {
+        let mut marker = Marker::default();
+
+        marker.mark_values(&self.registers);
+        marker.mark_values(&self.stack);
+
+        for frame in self.call_stack.iter() {
+            marker.mark_values(&frame.registers);
+        }
+
+        let coroutines = self
+            .active_coroutines
+            .iter()
+            .chain(self.threads.iter().flat_map(|thread| thread.active_coroutines.iter()))
+            .map(|active| Value::Coroutine(active.coroutine.clone()))
+            .collect::<Vec<_>>();
+        marker.mark_values(&coroutines);
+
+        for thread in self.threads.iter() {
+            marker.mark_context(&thread.context);
+
+            match &thread.state {
+                ThreadState::Finished(value) => marker.mark_values([value]),
+                ThreadState::Receiving(channel) => {
+                    marker.mark_values([&Value::Channel(channel.clone())])
+                }
+                _ => {}
+            }
+        }
+
+        self.heap.sweep(&marker);
+    }
+
+    pub fn gc_stats(&self) -> GcStats {
+        self.heap.stats
+    }
+
+    /// Suspends the current thread and continues with the next runnable thread, in round-robin order
+    fn switch_thread(&mut self) -> Result<()> {
+        self.slice_instructions = 0;
+
+        let count = self.threads.len();
+        let next = (1..=count)
+            .map(|offset| (self.current_thread + offset) % count)
+            .find(|id| self.threads[*id].is_runnable());
+
+        let next = match next {
+            Some(next) => next,
+            None => {
+                let blocked = self
+                    .threads
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, thread)| thread.is_blocked())
+                    .map(|(id, _)| id)
+                    .collect();
+
+                return Err(Error::Deadlock(blocked));
+            }
+        };
+
+        if next != self.current_thread {
+            self.swap_thread(self.current_thread);
+            self.swap_thread(next);
+            self.current_thread = next;
+        }
+
+        Ok(())
+    }
+
+    /// Swaps the state of the current line of execution with the state stored for a thread
+    fn swap_thread(&mut self, id: usize) {
+        let mut context = std::mem::take(&mut self.threads[id].context);
+        self.swap_context(&mut context);
+        self.threads[id].context = context;
+
+        std::mem::swap(&mut self.active_coroutines, &mut self.threads[id].active_coroutines);
+    }
+
+    /// Finishes the current thread with the given result, waking up every thread joining it
+    fn finish_thread(&mut self, value: Value) -> Result<()> {
+        let id = self.current_thread;
+
+        for thread in self.threads.iter_mut() {
+            if matches!(thread.state, ThreadState::Joining(target) if target == id) {
+                thread.state = ThreadState::Runnable;
+            }
+        }
+
+        self.threads[id].state = ThreadState::Finished(value);
+        self.switch_thread()
+    }
+
+    /// Calls the function at `address` in a new call frame whose registers start out as `registers`
+    fn call(&mut self, address: usize, registers: Vec<Value>, return_register: Register) -> Result<()> {
+        if self.call_stack.len() >= self.max_call_depth {
+            return Err(Error::StackOverflow);
+        }
+
+        let caller_registers = std::mem::replace(&mut self.registers, registers);
+
+        self.call_stack.push(CallFrame {
+            return_address: self.instruction_pointer + 1,
+            registers: caller_registers,
+            return_register,
+        });
+        self.instruction_pointer = address;
+
+        Ok(())
+    }
+
+    /// Swaps the state of the current line of execution with the given context
+    fn swap_context(&mut self, context: &mut Context) {
+        std::mem::swap(&mut self.instruction_pointer, &mut context.instruction_pointer);
+        std::mem::swap(&mut self.registers, &mut context.registers);
+        std::mem::swap(&mut self.stack, &mut context.stack);
+        std::mem::swap(&mut self.call_stack, &mut context.call_stack);
+        std::mem::swap(&mut self.handlers, &mut context.handlers);
+    }
+
+    /// Switches from the innermost running coroutine back to its resumer, returning the
+    /// resumer's register that receives the coroutine's result
+    fn leave_coroutine(&mut self, status: CoroutineStatus) -> Option<Register> {
+        let active = self.active_coroutines.pop()?;
+        let mut coroutine = active.coroutine.borrow_mut();
+
+        self.swap_context(&mut coroutine.context);
+        coroutine.status = status;
+
+        Some(active.register)
+    }
+
+    /// Delivers an exception to the innermost handler, unwinding the guest frames above it.
+    /// If there is no handler, `error` is returned to the host
+    fn throw(&mut self, value: Value, error: Error) -> Result<()> {
+        // an exception that escapes a coroutine finishes it, and is rethrown in its resumer
+        let handler = loop {
+            if let Some(handler) = self.handlers.pop() {
+                break handler;
+            }
+
+            if self.leave_coroutine(CoroutineStatus::Finished).is_none() {
+                return Err(error);
+            }
+        };
+
+        while self.call_stack.len() > handler.call_depth {
+            if let Some(frame) = self.call_stack.pop() {
+                self.registers = frame.registers;
+            }
+        }
+
+        self.stack.truncate(handler.stack_length);
+        self.set_register(handler.register, value);
+        self.instruction_pointer = handler.catch_address;
+
+        Ok(())
+    }
+
+    fn execute_instruction(&mut self, instruction: &Instruction) -> Result<()> {
+        match *instruction {
+            Instruction::LoadInt(register, value) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LoadInt"),
+                    line: 80,
+                });
+
+                self.set_register(register, Value::Int(value));
+                self.increment_ip();
+            }
+
+            Instruction::LoadUInt(register, value) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LoadUInt"),
+                    line: line!(),
+                });
+
+                self.set_register(register, Value::UInt(value));
+                self.increment_ip();
+            }
+
+            Instruction::LoadFlt(register, value) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LoadFlt"),
+                    line: 99,
+                });
+
+                self.set_register(register, Value::Float(value));
+                self.increment_ip();
+            }
+
+            Instruction::LoadStr(register, ref value) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LoadStr"),
+                    line: 109,
+                });
+
+                self.set_register(register, Value::String(value.clone()));
+                self.increment_ip();
+            }
+
+            Instruction::LoadBool(register, value) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LoadBool"),
+                    line: 119,
+                });
+
+                self.set_register(register, Value::Bool(value));
+                self.increment_ip();
+            }
+
+            Instruction::AddInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AddInt"),
+                    line: 129,
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    i64::checked_add,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::AddUInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AddUInt"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    u64::checked_add,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::AddFlt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AddFlt"),
+                    line: 141,
+                });
+
+                let a = self.get_float(source_register1)?;
+                let b = self.get_float(source_register2)?;
+
+                self.set_register(destination_register, Value::Float(a + b));
+                self.increment_ip();
+            }
+
+            Instruction::SubInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::SubInt"),
+                    line: 153,
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    i64::checked_sub,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::SubUInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::SubUInt"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    u64::checked_sub,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::SubFlt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::SubFlt"),
+                    line: 165,
+                });
+
+                let a = self.get_float(source_register1)?;
+                let b = self.get_float(source_register2)?;
+
+                self.set_register(destination_register, Value::Float(a - b));
+                self.increment_ip();
+            }
+
+            Instruction::MulInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MulInt"),
+                    line: 177,
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    i64::checked_mul,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::MulUInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MulUInt"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    u64::checked_mul,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::MulFlt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MulFlt"),
+                    line: 189,
+                });
+
+                let a = self.get_float(source_register1)?;
+                let b = self.get_float(source_register2)?;
+
+                self.set_register(destination_register, Value::Float(a * b));
+                self.increment_ip();
+            }
+
+            Instruction::DivInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::DivInt"),
+                    line: 201,
+                });
+
+                if self.get_int(source_register2)? == 0 {
+                    return Err(Error::DivisionByZero);
+                }
+
+                // i64::MIN / -1 overflows
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    i64::checked_div,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::DivUInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::DivUInt"),
+                    line: line!(),
+                });
+
+                if self.get_uint(source_register2)? == 0 {
+                    return Err(Error::DivisionByZero);
+                }
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    u64::checked_div,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::DivFlt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::DivFlt"),
+                    line: 218,
+                });
+
+                let a = self.get_float(source_register1)?;
+                let b = self.get_float(source_register2)?;
+
+                if b == 0.0 {
+                    return Err(Error::DivisionByZero);
+                } else {
+                    self.set_register(destination_register, Value::Float(a / b));
+                }
+
+                self.increment_ip();
+            }
+
+            Instruction::ModInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ModInt"),
+                    line: line!(),
+                });
+
+                if self.get_int(source_register2)? == 0 {
+                    return Err(Error::DivisionByZero);
+                }
+
+                // i64::MIN % -1 overflows
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    i64::checked_rem,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::NegInt(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::NegInt"),
+                    line: line!(),
+                });
+
+                let value = match self.get_int(source_register)?.checked_neg() {
+                    Some(value) => value,
+                    None => return Err(Error::IntegerOverflow),
+                };
+
+                self.set_register(destination_register, Value::Int(value));
+                self.increment_ip();
+            }
+
+            Instruction::NegFlt(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::NegFlt"),
+                    line: line!(),
+                });
+
+                let value = self.get_float(source_register)?;
+
+                self.set_register(destination_register, Value::Float(-value));
+                self.increment_ip();
+            }
+
+            Instruction::BitAnd(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BitAnd"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a & b),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::BitOr(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BitOr"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a | b),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::BitXor(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BitXor"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a ^ b),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::BitNot(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BitNot"),
+                    line: line!(),
+                });
+
+                let value = self.get_int(source_register)?;
+
+                self.set_register(destination_register, Value::Int(!value));
+                self.increment_ip();
+            }
+
+            Instruction::Shl(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Shl"),
+                    line: line!(),
+                });
+
+                let value = self.get_int(source_register1)?;
+                let amount = self.get_shift_amount(source_register2)?;
+
+                self.set_register(destination_register, Value::Int(value << amount));
+                self.increment_ip();
+            }
+
+            Instruction::Shr(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Shr"),
+                    line: line!(),
+                });
+
+                let value = self.get_int(source_register1)?;
+                let amount = self.get_shift_amount(source_register2)?;
+
+                self.set_register(destination_register, Value::Int(value >> amount));
+                self.increment_ip();
+            }
+
+            Instruction::ShrLogical(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ShrLogical"),
+                    line: line!(),
+                });
+
+                let value = self.get_int(source_register1)?;
+                let amount = self.get_shift_amount(source_register2)?;
+
+                self.set_register(destination_register, Value::Int(((value as u64) >> amount) as i64));
+                self.increment_ip();
+            }
+
+            Instruction::AddIntWrap(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AddIntWrap"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.wrapping_add(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::AddIntSat(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AddIntSat"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.saturating_add(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::SubIntWrap(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::SubIntWrap"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.wrapping_sub(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::SubIntSat(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::SubIntSat"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.saturating_sub(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::MulIntWrap(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MulIntWrap"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.wrapping_mul(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::MulIntSat(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MulIntSat"),
+                    line: line!(),
+                });
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.saturating_mul(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::DivIntWrap(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::DivIntWrap"),
+                    line: line!(),
+                });
+
+                if self.get_int(source_register2)? == 0 {
+                    return Err(Error::DivisionByZero);
+                }
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.wrapping_div(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::DivIntSat(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::DivIntSat"),
+                    line: line!(),
+                });
+
+                if self.get_int(source_register2)? == 0 {
+                    return Err(Error::DivisionByZero);
+                }
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.saturating_div(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::ModIntWrap(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ModIntWrap"),
+                    line: line!(),
+                });
+
+                if self.get_int(source_register2)? == 0 {
+                    return Err(Error::DivisionByZero);
+                }
+
+                self.int_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.wrapping_rem(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::NegIntWrap(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::NegIntWrap"),
+                    line: line!(),
+                });
+
+                let value = self.get_int(source_register)?.wrapping_neg();
+
+                self.set_register(destination_register, Value::Int(value));
+                self.increment_ip();
+            }
+
+            Instruction::NegIntSat(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::NegIntSat"),
+                    line: line!(),
+                });
+
+                let value = self.get_int(source_register)?.saturating_neg();
+
+                self.set_register(destination_register, Value::Int(value));
+                self.increment_ip();
+            }
+
+            Instruction::AddUIntWrap(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AddUIntWrap"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.wrapping_add(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::AddUIntSat(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AddUIntSat"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.saturating_add(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::SubUIntWrap(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::SubUIntWrap"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.wrapping_sub(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::SubUIntSat(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::SubUIntSat"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.saturating_sub(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::MulUIntWrap(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MulUIntWrap"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.wrapping_mul(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::MulUIntSat(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MulUIntSat"),
+                    line: line!(),
+                });
+
+                self.uint_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    |a, b| Some(a.saturating_mul(b)),
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::ConcatStrings(
+                destination_register,
+                source_register1,
+                source_register2,
+            ) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ConcatStrings"),
+                    line: 235,
+                });
+
+                let a = self.get_string(source_register1)?;
+                let b = self.get_string(source_register2)?;
+                let result = Value::String(format!("{a}{b}"));
+
+                self.set_register(destination_register, result);
+                self.increment_ip();
+            }
+
+            Instruction::AndBool(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AndBool"),
+                    line: 247,
+                });
+
+                let a = self.get_bool(source_register1)?;
+                let b = self.get_bool(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a && b));
+                self.increment_ip();
+            }
+
+            Instruction::OrBool(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::OrBool"),
+                    line: 259,
+                });
+
+                let a = self.get_bool(source_register1)?;
+                let b = self.get_bool(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a || b));
+                self.increment_ip();
+            }
+
+            Instruction::NotBool(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::NotBool"),
+                    line: line!(),
+                });
+
+                let value = self.get_bool(source_register)?;
+
+                self.set_register(destination_register, Value::Bool(!value));
+                self.increment_ip();
+            }
+
+            Instruction::XorBool(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::XorBool"),
+                    line: line!(),
+                });
+
+                let a = self.get_bool(source_register1)?;
+                let b = self.get_bool(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a ^ b));
+                self.increment_ip();
+            }
+
+            Instruction::LtInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LtInt"),
+                    line: 271,
+                });
+
+                let a = self.get_int(source_register1)?;
+                let b = self.get_int(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a < b));
+                self.increment_ip();
+            }
+
+            Instruction::LtUInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LtUInt"),
+                    line: line!(),
+                });
+
+                let a = self.get_uint(source_register1)?;
+                let b = self.get_uint(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a < b));
+                self.increment_ip();
+            }
+
+            Instruction::GtInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::GtInt"),
+                    line: 283,
+                });
+
+                let a = self.get_int(source_register1)?;
+                let b = self.get_int(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a > b));
+                self.increment_ip();
+            }
+
+            Instruction::GtUInt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::GtUInt"),
+                    line: line!(),
+                });
+
+                let a = self.get_uint(source_register1)?;
+                let b = self.get_uint(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a > b));
+                self.increment_ip();
+            }
+
+            Instruction::LtFlt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LtFlt"),
+                    line: 295,
+                });
+
+                let a = self.get_float(source_register1)?;
+                let b = self.get_float(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a < b));
+                self.increment_ip();
+            }
+
+            Instruction::GtFlt(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::GtFlt"),
+                    line: 307,
+                });
+
+                let a = self.get_float(source_register1)?;
+                let b = self.get_float(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a > b));
+                self.increment_ip();
+            }
+
+            Instruction::EqBool(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::EqBool"),
+                    line: 319,
+                });
+
+                let a = self.get_bool(source_register1)?;
+                let b = self.get_bool(source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(a == b));
+                self.increment_ip();
+            }
+
+            Instruction::Eq(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Eq"),
+                    line: line!(),
+                });
+
+                let equal = self.values_equal(source_register1, source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(equal));
+                self.increment_ip();
+            }
+
+            Instruction::Ne(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Ne"),
+                    line: line!(),
+                });
+
+                let equal = self.values_equal(source_register1, source_register2)?;
+
+                self.set_register(destination_register, Value::Bool(!equal));
+                self.increment_ip();
+            }
+
+            Instruction::Le(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Le"),
+                    line: line!(),
+                });
+
+                let ordering = self.compare_values(source_register1, source_register2)?;
+
+                self.set_register(
+                    destination_register,
+                    Value::Bool(ordering.is_some_and(Ordering::is_le)),
+                );
+                self.increment_ip();
+            }
+
+            Instruction::Ge(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Ge"),
+                    line: line!(),
+                });
+
+                let ordering = self.compare_values(source_register1, source_register2)?;
+
+                self.set_register(
+                    destination_register,
+                    Value::Bool(ordering.is_some_and(Ordering::is_ge)),
+                );
+                self.increment_ip();
+            }
+
+            Instruction::Cmp(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Cmp"),
+                    line: line!(),
+                });
+
+                let ordering = match self.compare_values(source_register1, source_register2)? {
+                    Some(ordering) => ordering,
+                    None => return Err(Error::UnorderedNaN),
+                };
+
+                self.set_register(destination_register, Value::Int(ordering as i64));
+                self.increment_ip();
+            }
+
+            Instruction::Print(ref value_or_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Print"),
+                    line: 331,
+                });
+
+                match value_or_register {
+                    ValueOrRegister::Value(string) => print!("{}", string),
+                    ValueOrRegister::Register(register) => {
+                        let value = self.register(*register);
+                        print!("{}", value);
+                    }
+                }
+
+                self.increment_ip();
+            }
+
+            Instruction::CreateArray(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::CreateArray"),
+                    line: 347,
+                });
+
+                let array = self.allocate(Value::Array(Array::default()));
+
+                self.set_register(register, array);
+                self.increment_ip();
+            }
+
+            Instruction::ArrayAdd(register, ref value) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArrayAdd"),
+                    line: 357,
+                });
+
+                // literals are copied so that the program itself is never mutated
+                let value = self.allocate(value.deep_clone());
+
+                self.get_array(register)?.borrow_mut().push(value);
+                self.increment_ip();
+            }
+
+            Instruction::GetArrayElemPtr(destination_register, array_register, index) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::GetArrayElemPtr"),
+                    line: 371,
+                });
+
+                let value = self.get_array(array_register)?.borrow().get(index).cloned();
+
+                match value {
+                    Some(value) => self.set_register(destination_register, value),
+                    None => {
+                        let length = self.get_array(array_register)?.borrow().len();
+
+                        return Err(Error::ArrayIndexOutOfBounds(index as i64, length));
+                    }
+                }
+
+                self.increment_ip();
+            }
+
+            Instruction::GetArrayLength(array_register, destination_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::GetArrayLength"),
+                    line: 396,
+                });
+
+                // a Vec never holds more than isize::MAX elements, so this cannot truncate
+                let length = self.get_array(array_register)?.borrow().len() as i64;
+
+                self.set_register(destination_register, Value::Int(length));
+
+                self.increment_ip();
+            }
+
+            Instruction::Push(ref value) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Push"),
+                    line: 411,
+                });
+
+                let value = self.allocate(value.deep_clone());
+
+                self.stack.push(value);
+                self.increment_ip();
+            }
+
+            Instruction::Pop(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Pop"),
+                    line: 421,
+                });
+
+                if let Some(value) = self.stack.pop() {
+                    self.set_register(register, value);
+                } else {
+                    return Err(Error::StackUnderflow);
+                }
+
+                self.increment_ip();
+            }
+
+            Instruction::CopyReg(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::CopyReg"),
+                    line: 484,
+                });
+
+                let source = self.register(source_register).clone();
+
+                self.set_register(destination_register, source);
+                self.increment_ip();
+            }
+
+            Instruction::ArrayPush(array_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArrayPush"),
+                    line: line!(),
+                });
+
+                let value = self.register(source_register).clone();
+
+                self.get_array(array_register)?.borrow_mut().push(value);
+                self.increment_ip();
+            }
+
+            Instruction::ArrayGet(destination_register, array_register, index_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArrayGet"),
+                    line: line!(),
+                });
+
+                let index = self.get_int(index_register)?;
+                let array = self.get_array(array_register)?;
+                let value = {
+                    let array = array.borrow();
+                    let index = Self::check_array_index(index, array.len(), array.len())?;
+
+                    array[index].clone()
+                };
+
+                self.set_register(destination_register, value);
+                self.increment_ip();
+            }
+
+            Instruction::ArraySet(array_register, index_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArraySet"),
+                    line: line!(),
+                });
+
+                let index = self.get_int(index_register)?;
+                let value = self.register(source_register).clone();
+                let array = self.get_array(array_register)?;
+                let mut array = array.borrow_mut();
+                let index = Self::check_array_index(index, array.len(), array.len())?;
+
+                array[index] = value;
+                self.increment_ip();
+            }
+
+            Instruction::ArrayPop(destination_register, array_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArrayPop"),
+                    line: line!(),
+                });
+
+                let value = match self.get_array(array_register)?.borrow_mut().pop() {
+                    Some(value) => value,
+                    None => return Err(Error::ArrayIndexOutOfBounds(-1, 0)),
+                };
+
+                self.set_register(destination_register, value);
+                self.increment_ip();
+            }
+
+            Instruction::ArrayInsert(array_register, index_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArrayInsert"),
+                    line: line!(),
+                });
+
+                let index = self.get_int(index_register)?;
+                let value = self.register(source_register).clone();
+                let array = self.get_array(array_register)?;
+                let mut array = array.borrow_mut();
+                let index = Self::check_array_index(index, array.len() + 1, array.len())?;
+
+                array.insert(index, value);
+                self.increment_ip();
+            }
+
+            Instruction::ArrayRemove(destination_register, array_register, index_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArrayRemove"),
+                    line: line!(),
+                });
+
+                let index = self.get_int(index_register)?;
+                let array = self.get_array(array_register)?;
+                let value = {
+                    let mut array = array.borrow_mut();
+                    let index = Self::check_array_index(index, array.len(), array.len())?;
+
+                    array.remove(index)
+                };
+
+                self.set_register(destination_register, value);
+                self.increment_ip();
+            }
+
+            Instruction::ArraySlice(destination_register, array_register, start_register, end_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArraySlice"),
+                    line: line!(),
+                });
+
+                let start = self.get_int(start_register)?;
+                let end = self.get_int(end_register)?;
+                let slice = {
+                    let array = self.get_array(array_register)?;
+                    let array = array.borrow();
+                    let end = Self::check_array_index(end, array.len() + 1, array.len())?;
+                    let start = Self::check_array_index(start, end + 1, array.len())?;
+
+                    array[start..end].to_vec()
+                };
+                let slice = self.allocate(Value::new_array(slice));
+
+                self.set_register(destination_register, slice);
+                self.increment_ip();
+            }
+
+            Instruction::ArrayConcat(destination_register, array_register1, array_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ArrayConcat"),
+                    line: line!(),
+                });
+
+                let mut values = self.get_array(array_register1)?.borrow().clone();
+                values.extend(self.get_array(array_register2)?.borrow().iter().cloned());
+                let values = self.allocate(Value::new_array(values));
+
+                self.set_register(destination_register, values);
+                self.increment_ip();
+            }
+
+            Instruction::CreateMap(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::CreateMap"),
+                    line: line!(),
+                });
+
+                let map = self.allocate(Value::Map(Map::default()));
+
+                self.set_register(register, map);
+                self.increment_ip();
+            }
+
+            Instruction::MapSet(map_register, key_register, value_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MapSet"),
+                    line: line!(),
+                });
+
+                let key = self.get_map_key(key_register)?;
+                let value = self.register(value_register).clone();
+
+                self.get_map(map_register)?.borrow_mut().insert(key, value);
+
+                self.increment_ip();
+            }
+
+            Instruction::MapGet(destination_register, map_register, key_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MapGet"),
+                    line: line!(),
+                });
+
+                let key = self.get_map_key(key_register)?;
+                let value = self.get_map(map_register)?.borrow().get(&key).cloned();
+
+                match value {
+                    Some(value) => self.set_register(destination_register, value),
+                    None => return Err(Error::KeyNotFound(Value::from(key).to_string(), map_register)),
+                }
+
+                self.increment_ip();
+            }
+
+            Instruction::MapHas(destination_register, map_register, key_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MapHas"),
+                    line: line!(),
+                });
+
+                let key = self.get_map_key(key_register)?;
+                let has_key = self.get_map(map_register)?.borrow().contains_key(&key);
+
+                self.set_register(destination_register, Value::Bool(has_key));
+                self.increment_ip();
+            }
+
+            Instruction::MapRemove(destination_register, map_register, key_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MapRemove"),
+                    line: line!(),
+                });
+
+                let key = self.get_map_key(key_register)?;
+                let value = self.get_map(map_register)?.borrow_mut().remove(&key);
+
+                match value {
+                    Some(value) => self.set_register(destination_register, value),
+                    None => return Err(Error::KeyNotFound(Value::from(key).to_string(), map_register)),
+                }
+
+                self.increment_ip();
+            }
+
+            Instruction::MapKeys(destination_register, map_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MapKeys"),
+                    line: line!(),
+                });
+
+                let keys = self
+                    .get_map(map_register)?
+                    .borrow()
+                    .keys()
+                    .cloned()
+                    .map(Value::from)
+                    .collect();
+                let keys = self.allocate(Value::new_array(keys));
+
+                self.set_register(destination_register, keys);
+                self.increment_ip();
+            }
+
+            Instruction::MapLength(destination_register, map_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MapLength"),
+                    line: line!(),
+                });
+
+                let length = self.get_map(map_register)?.borrow().len() as i64;
+
+                self.set_register(destination_register, Value::Int(length));
+                self.increment_ip();
+            }
+
+            Instruction::IntToFloat(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::IntToFloat"),
+                    line: line!(),
+                });
+
+                let value = self.get_int(source_register)?;
+
+                self.set_register(destination_register, Value::Float(value as f64));
+                self.increment_ip();
+            }
+
+            Instruction::FloatToInt(destination_register, source_register, rounding_mode) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::FloatToInt"),
+                    line: line!(),
+                });
+
+                let value = self.get_float(source_register)?;
+                let rounded = rounding_mode.apply(value);
+
+                // `i64::MAX as f64` is 2^63, which is already out of range
+                if !(i64::MIN as f64..i64::MAX as f64).contains(&rounded) {
+                    return Err(Error::FloatOutOfRange(value));
+                }
+
+                self.set_register(destination_register, Value::Int(rounded as i64));
+                self.increment_ip();
+            }
+
+            Instruction::IntToUInt(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::IntToUInt"),
+                    line: line!(),
+                });
+
+                let value = self.get_int(source_register)?;
+                let value = u64::try_from(value).map_err(|_| Error::IntegerOverflow)?;
+
+                self.set_register(destination_register, Value::UInt(value));
+                self.increment_ip();
+            }
+
+            Instruction::UIntToInt(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::UIntToInt"),
+                    line: line!(),
+                });
+
+                let value = self.get_uint(source_register)?;
+                let value = i64::try_from(value).map_err(|_| Error::IntegerOverflow)?;
+
+                self.set_register(destination_register, Value::Int(value));
+                self.increment_ip();
+            }
+
+            Instruction::ToString(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ToString"),
+                    line: line!(),
+                });
+
+                let string = self.register(source_register).to_string();
+
+                self.set_register(destination_register, Value::String(string));
+                self.increment_ip();
+            }
+
+            Instruction::ParseInt(destination_register, source_register, radix) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ParseInt"),
+                    line: line!(),
+                });
+
+                let radix = radix.unwrap_or(10);
+
+                if !(2..=36).contains(&radix) {
+                    return Err(Error::InvalidRadix(radix));
+                }
+
+                let string = self.get_string(source_register)?;
+                let value = match i64::from_str_radix(&string, radix) {
+                    Ok(value) => value,
+                    Err(_) => return Err(Error::InvalidNumber(string, String::from("int"))),
+                };
+
+                self.set_register(destination_register, Value::Int(value));
+                self.increment_ip();
+            }
+
+            Instruction::ParseFloat(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ParseFloat"),
+                    line: line!(),
+                });
+
+                let string = self.get_string(source_register)?;
+                let value = match string.parse::<f64>() {
+                    Ok(value) => value,
+                    Err(_) => return Err(Error::InvalidNumber(string, String::from("float"))),
+                };
+
+                self.set_register(destination_register, Value::Float(value));
+                self.increment_ip();
+            }
+
+            Instruction::TypeOf(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::TypeOf"),
+                    line: line!(),
+                });
+
+                let type_name = self.register(source_register).type_name();
+
+                self.set_register(destination_register, Value::String(type_name.to_string()));
+                self.increment_ip();
+            }
+
+            Instruction::IsType(destination_register, source_register, value_type) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::IsType"),
+                    line: line!(),
+                });
+
+                let is_type = self.register(source_register).value_type() == value_type;
+
+                self.set_register(destination_register, Value::Bool(is_type));
+                self.increment_ip();
+            }
+
+            Instruction::AssertType(register, value_type) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::AssertType"),
+                    line: line!(),
+                });
+
+                if self.register(register).value_type() != value_type {
+                    return Err(Error::ExpectedType(value_type.to_string(), register));
+                }
+
+                self.increment_ip();
+            }
+
+            Instruction::Sqrt(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Sqrt"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::sqrt)?;
+                self.increment_ip();
+            }
+
+            Instruction::Pow(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Pow"),
+                    line: line!(),
+                });
+
+                self.float_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    f64::powf,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::Exp(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Exp"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::exp)?;
+                self.increment_ip();
+            }
+
+            Instruction::Ln(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Ln"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::ln)?;
+                self.increment_ip();
+            }
+
+            Instruction::Sin(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Sin"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::sin)?;
+                self.increment_ip();
+            }
+
+            Instruction::Cos(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Cos"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::cos)?;
+                self.increment_ip();
+            }
+
+            Instruction::Tan(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Tan"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::tan)?;
+                self.increment_ip();
+            }
+
+            Instruction::Atan2(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Atan2"),
+                    line: line!(),
+                });
+
+                self.float_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    f64::atan2,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::Floor(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Floor"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::floor)?;
+                self.increment_ip();
+            }
+
+            Instruction::Ceil(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Ceil"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::ceil)?;
+                self.increment_ip();
+            }
+
+            Instruction::Round(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Round"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::round)?;
+                self.increment_ip();
+            }
+
+            Instruction::Abs(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Abs"),
+                    line: line!(),
+                });
+
+                self.float_function(destination_register, source_register, f64::abs)?;
+                self.increment_ip();
+            }
+
+            Instruction::Min(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Min"),
+                    line: line!(),
+                });
+
+                self.float_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    f64::min,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::Max(destination_register, source_register1, source_register2) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Max"),
+                    line: line!(),
+                });
+
+                self.float_operation(
+                    destination_register,
+                    source_register1,
+                    source_register2,
+                    f64::max,
+                )?;
+                self.increment_ip();
+            }
+
+            Instruction::IsNaN(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::IsNaN"),
+                    line: line!(),
+                });
+
+                let value = self.get_float(source_register)?;
+
+                self.set_register(destination_register, Value::Bool(value.is_nan()));
+                self.increment_ip();
+            }
+
+            Instruction::StrLen(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrLen"),
+                    line: line!(),
+                });
+
+                let length = self.get_string(source_register)?.chars().count() as i64;
+
+                self.set_register(destination_register, Value::Int(length));
+                self.increment_ip();
+            }
+
+            Instruction::Substring(destination_register, source_register, start_register, end_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Substring"),
+                    line: line!(),
+                });
+
+                let start = self.get_int(start_register)?;
+                let end = self.get_int(end_register)?;
+                let string = self.get_string(source_register)?;
+                let range = Self::check_range(start, end, string.chars().count())?;
+                let substring = string.chars().skip(range.start).take(range.len()).collect();
+
+                self.set_register(destination_register, Value::String(substring));
+                self.increment_ip();
+            }
+
+            Instruction::StrIndexOf(destination_register, source_register, pattern_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrIndexOf"),
+                    line: line!(),
+                });
+
+                let string = self.get_string(source_register)?;
+                let pattern = self.get_string(pattern_register)?;
+                let index = match string.find(&pattern) {
+                    Some(byte_index) => string[..byte_index].chars().count() as i64,
+                    None => -1,
+                };
+
+                self.set_register(destination_register, Value::Int(index));
+                self.increment_ip();
+            }
+
+            Instruction::StrSplit(destination_register, source_register, separator_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrSplit"),
+                    line: line!(),
+                });
+
+                let string = self.get_string(source_register)?;
+                let separator = self.get_string(separator_register)?;
+                let parts = if separator.is_empty() {
+                    string.chars().map(|char| Value::String(char.to_string())).collect()
+                } else {
+                    string
+                        .split(&separator)
+                        .map(|part| Value::String(part.to_owned()))
+                        .collect()
+                };
+
+                let parts = self.allocate(Value::new_array(parts));
+
+                self.set_register(destination_register, parts);
+                self.increment_ip();
+            }
+
+            Instruction::StrReplace(destination_register, source_register, from_register, to_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrReplace"),
+                    line: line!(),
+                });
+
+                let string = self.get_string(source_register)?;
+                let from = self.get_string(from_register)?;
+                let to = self.get_string(to_register)?;
+
+                self.set_register(destination_register, Value::String(string.replace(&from, &to)));
+                self.increment_ip();
+            }
+
+            Instruction::StrTrim(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrTrim"),
+                    line: line!(),
+                });
+
+                let string = self.get_string(source_register)?;
+
+                self.set_register(destination_register, Value::String(string.trim().to_owned()));
+                self.increment_ip();
+            }
+
+            Instruction::StrUpper(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrUpper"),
+                    line: line!(),
+                });
+
+                let string = self.get_string(source_register)?;
+
+                self.set_register(destination_register, Value::String(string.to_uppercase()));
+                self.increment_ip();
+            }
+
+            Instruction::StrLower(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrLower"),
+                    line: line!(),
+                });
+
+                let string = self.get_string(source_register)?;
+
+                self.set_register(destination_register, Value::String(string.to_lowercase()));
+                self.increment_ip();
+            }
+
+            Instruction::StrStartsWith(destination_register, source_register, prefix_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrStartsWith"),
+                    line: line!(),
+                });
+
+                let string = self.get_string(source_register)?;
+                let prefix = self.get_string(prefix_register)?;
+
+                self.set_register(destination_register, Value::Bool(string.starts_with(&prefix)));
+                self.increment_ip();
+            }
+
+            Instruction::CharAt(destination_register, source_register, index_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::CharAt"),
+                    line: line!(),
+                });
+
+                let char = self.get_char(source_register, index_register)?;
+
+                self.set_register(destination_register, Value::String(char.to_string()));
+                self.increment_ip();
+            }
+
+            Instruction::CodepointAt(destination_register, source_register, index_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::CodepointAt"),
+                    line: line!(),
+                });
+
+                let char = self.get_char(source_register, index_register)?;
+
+                self.set_register(destination_register, Value::Int(char as i64));
+                self.increment_ip();
+            }
+
+            Instruction::FromCodepoint(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::FromCodepoint"),
+                    line: line!(),
+                });
+
+                let codepoint = self.get_int(source_register)?;
+                let char = match u32::try_from(codepoint).ok().and_then(char::from_u32) {
+                    Some(char) => char,
+                    None => return Err(Error::InvalidCodepoint(codepoint)),
+                };
+
+                self.set_register(destination_register, Value::String(char.to_string()));
+                self.increment_ip();
+            }
+
+            Instruction::CreateBytes(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::CreateBytes"),
+                    line: line!(),
+                });
+
+                self.set_register(register, Value::Bytes(Vec::new()));
+                self.increment_ip();
+            }
+
+            Instruction::BytesGet(destination_register, bytes_register, index_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BytesGet"),
+                    line: line!(),
+                });
+
+                let index = self.get_int(index_register)?;
+                let bytes = self.get_bytes(bytes_register)?;
+                let byte = match usize::try_from(index).ok().and_then(|index| bytes.get(index)) {
+                    Some(byte) => *byte,
+                    None => return Err(Error::IndexOutOfBounds(index, bytes.len())),
+                };
+
+                self.set_register(destination_register, Value::Int(byte as i64));
+                self.increment_ip();
+            }
+
+            Instruction::BytesSlice(destination_register, bytes_register, start_register, end_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BytesSlice"),
+                    line: line!(),
+                });
+
+                let start = self.get_int(start_register)?;
+                let end = self.get_int(end_register)?;
+                let bytes = self.get_bytes(bytes_register)?;
+                let range = Self::check_range(start, end, bytes.len())?;
+                let slice = bytes[range].to_vec();
+
+                self.set_register(destination_register, Value::Bytes(slice));
+                self.increment_ip();
+            }
+
+            Instruction::BytesAppend(bytes_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BytesAppend"),
+                    line: line!(),
+                });
+
+                let appended = match self.register(source_register) {
+                    Value::Int(value) => match u8::try_from(*value) {
+                        Ok(byte) => vec![byte],
+                        Err(_) => return Err(Error::InvalidByte(*value)),
+                    },
+                    Value::Bytes(bytes) => bytes.clone(),
+                    _ => return Err(Error::ExpectedType(String::from("int or bytes"), source_register)),
+                };
+
+                if let Value::Bytes(bytes) = self.register_mut(bytes_register) {
+                    bytes.extend(appended);
+                } else {
+                    return Err(Error::ExpectedType(String::from("bytes"), bytes_register));
+                }
+
+                self.increment_ip();
+            }
+
+            Instruction::BytesLength(destination_register, bytes_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BytesLength"),
+                    line: line!(),
+                });
+
+                let length = self.get_bytes(bytes_register)?.len() as i64;
+
+                self.set_register(destination_register, Value::Int(length));
+                self.increment_ip();
+            }
+
+            Instruction::StrToBytes(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::StrToBytes"),
+                    line: line!(),
+                });
+
+                let bytes = self.get_string(source_register)?.into_bytes();
+
+                self.set_register(destination_register, Value::Bytes(bytes));
+                self.increment_ip();
+            }
+
+            Instruction::BytesToStr(destination_register, source_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::BytesToStr"),
+                    line: line!(),
+                });
+
+                let bytes = self.get_bytes(source_register)?.to_vec();
+                let string = match String::from_utf8(bytes) {
+                    Ok(string) => string,
+                    Err(_) => return Err(Error::InvalidUtf8(source_register)),
+                };
+
+                self.set_register(destination_register, Value::String(string));
+                self.increment_ip();
+            }
+
+            Instruction::DefineStruct(..) => {
+                self.increment_ip();
+            }
+
+            Instruction::NewStruct(register, ref name) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::NewStruct"),
+                    line: line!(),
+                });
+
+                let struct_type = match self.structs.get(name) {
+                    Some(struct_type) => struct_type.clone(),
+                    None => return Err(Error::StructNotDefined(name.clone())),
+                };
+                let values = Rc::new(RefCell::new(vec![Value::Null; struct_type.fields.len()]));
+                let instance = self.allocate(Value::Struct(struct_type, values));
+
+                self.set_register(register, instance);
+                self.increment_ip();
+            }
+
+            Instruction::GetField(destination_register, struct_register, ref field) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::GetField"),
+                    line: line!(),
+                });
 
-        Self {
-            registers: regs,
-            stack: vec![],
-            frames: vec![],
-            instruction_pointer: 0,
-        }
-    }
+                let index = self.field_index(struct_register, field)?;
 
-    fn format_stack_frame(&mut self) -> String {
-        let mut result = String::new();
+                if let Value::Struct(_, values) = self.register(struct_register) {
+                    let value = values.borrow()[index].clone();
+                    self.set_register(destination_register, value);
+                }
 
-        for frame in self.frames.iter() {
-            if frame.file_name.starts_with("boltvm") {
-                result.push_str(&format!(
-                    "	in '{}' at '{}', line {}\n",
-                    frame.file_name, frame.function_name, frame.line
-                ));
+                self.increment_ip();
             }
-        }
 
-        result
-    }
+            Instruction::SetField(struct_register, ref field, value_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::SetField"),
+                    line: line!(),
+                });
 
-    pub fn handle_error(&mut self) {
-        let backtrace = Backtrace::new();
-        let frames = backtrace.frames();
+                let index = self.field_index(struct_register, field)?;
+                let value = self.register(value_register).clone();
 
-        let mut converted_frames = vec![];
-        for frame in frames {
-            if let Some(symbol) = frame.symbols().get(0) {
-                if let Some(function_name) = symbol.name() {
-                    if let Some(file) = symbol.filename() {
-                        if let Some(line) = symbol.lineno() {
-                            converted_frames.push(StackFrame {
-                                file_name: file.file_name().unwrap().to_str().unwrap().to_owned(),
-                                function_name: function_name.to_string(),
-                                line,
-                            });
-                        }
-                    }
+                if let Value::Struct(_, values) = self.register(struct_register) {
+                    values.borrow_mut()[index] = value;
                 }
+
+                self.increment_ip();
             }
-        }
 
-        self.frames.extend(converted_frames);
-        let stack_trace = self.format_stack_frame();
-        if stack_trace.is_empty() {
-            println!("	no stacktrace");
-        } else {
-            println!("{stack_trace}");
-        }
+            Instruction::Label(_) => {
+                self.increment_ip();
+            }
 
-        self.frames.clear();
-    }
+            Instruction::Jump(ref label) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Jump"),
+                    line: line!(),
+                });
 
-    fn increment_ip(&mut self) {
-        self.instruction_pointer += 1;
-    }
+                self.instruction_pointer = self.label_address(label)?;
+            }
 
-    pub fn execute(&mut self, program: Vec<Instruction>) -> Result<()> {
-        self.frames.push(StackFrame {
-            file_name: String::from("boltvm"),
-            function_name: String::from("execute()"),
-            line: 80,
-        });
+            Instruction::JumpAddr(ref address) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::JumpAddr"),
+                    line: line!(),
+                });
 
-        for instruction in program {
-            match instruction {
-                Instruction::LoadInt(register, value) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::LoadInt"),
-                        line: 80,
-                    });
-
-                    self.registers[register.as_index()] = Value::Int(value);
-                    self.increment_ip();
-                }
+                self.instruction_pointer = self.check_jump_target(address.0)?;
+            }
 
-                Instruction::LoadFlt(register, value) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::LoadFlt"),
-                        line: 99,
-                    });
+            Instruction::JumpIfTrue(register, ref label) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::JumpIfTrue"),
+                    line: line!(),
+                });
 
-                    self.registers[register.as_index()] = Value::Float(value);
+                if self.register(register).is_truthy() {
+                    self.instruction_pointer = self.label_address(label)?;
+                } else {
                     self.increment_ip();
                 }
+            }
 
-                Instruction::LoadStr(register, value) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::LoadStr"),
-                        line: 109,
-                    });
+            Instruction::JumpIfFalse(register, ref label) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::JumpIfFalse"),
+                    line: line!(),
+                });
 
-                    self.registers[register.as_index()] = Value::String(value);
+                if self.register(register).is_truthy() {
                     self.increment_ip();
+                } else {
+                    self.instruction_pointer = self.label_address(label)?;
                 }
+            }
 
-                Instruction::LoadBool(register, value) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::LoadBool"),
-                        line: 119,
-                    });
+            Instruction::JumpReg(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::JumpReg"),
+                    line: line!(),
+                });
+
+                let value = self.register(register);
+                let address = match value.as_jump_target() {
+                    Some(address) => address,
+                    None => {
+                        let value = self.get_int(register)?;
+                        return Err(Error::InvalidJumpTarget(value));
+                    }
+                };
 
-                    self.registers[register.as_index()] = Value::Bool(value);
-                    self.increment_ip();
-                }
+                self.instruction_pointer = self.check_jump_target(address)?;
+            }
 
-                Instruction::AddInt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::AddInt"),
-                        line: 129,
-                    });
+            Instruction::Switch(register, ref labels, ref default) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Switch"),
+                    line: line!(),
+                });
+
+                // the labels of the table are resolved before execution, so picking
+                // one costs the same however large the table is
+                let index = self.get_int(register)?;
+                let label = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| labels.get(index))
+                    .unwrap_or(default);
+
+                self.instruction_pointer = self.label_address(label)?;
+            }
 
-                    let a = self.get_int(source_register1).unwrap();
-                    let b = self.get_int(source_register2).unwrap();
+            Instruction::Call(ref label, ref arguments, return_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Call"),
+                    line: line!(),
+                });
 
-                    self.registers[destination_register.as_index()] = Value::Int(a + b);
-                    self.increment_ip();
-                }
+                let address = self.label_address(label)?;
+                let arguments = self.read_registers(arguments);
 
-                Instruction::AddFlt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::AddFlt"),
-                        line: 141,
-                    });
+                self.call(address, arguments, return_register)?;
+            }
 
-                    let a = self.get_float(source_register1).unwrap();
-                    let b = self.get_float(source_register2).unwrap();
+            Instruction::CallValue(function_register, ref arguments, return_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::CallValue"),
+                    line: line!(),
+                });
+
+                let (label, arity, captures) = match self.register(function_register) {
+                    Value::Function(label, arity) => (label.clone(), *arity, vec![]),
+                    Value::Closure(label, arity, captures) => {
+                        (label.clone(), *arity, captures.clone())
+                    }
+                    _ => {
+                        return Err(Error::ExpectedType(
+                            String::from("function"),
+                            function_register,
+                        ))
+                    }
+                };
 
-                    self.registers[destination_register.as_index()] = Value::Float(a + b);
-                    self.increment_ip();
+                if arguments.len() != arity as usize {
+                    return Err(Error::ArityMismatch(arity, arguments.len()));
                 }
 
-                Instruction::SubInt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::SubInt"),
-                        line: 153,
-                    });
+                // captured values follow the arguments in the callee's registers
+                let address = self.label_address(&label)?;
+                let mut arguments = self.read_registers(arguments);
+                arguments.extend(captures);
 
-                    let a = self.get_int(source_register1).unwrap();
-                    let b = self.get_int(source_register2).unwrap();
+                self.call(address, arguments, return_register)?;
+            }
 
-                    self.registers[destination_register.as_index()] = Value::Int(a - b);
-                    self.increment_ip();
+            Instruction::TailCall(ref label, ref arguments) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::TailCall"),
+                    line: line!(),
+                });
+
+                // at the top level of the program there is no frame to reuse, and the
+                // callee would have no caller to return to. the body of a coroutine
+                // returns to its resumer instead, and the body of a spawned thread to
+                // its joiners. a handler installed by the current function would
+                // catch exceptions with the callee's registers
+                let depth = self.call_stack.len();
+                let has_caller = depth > 0
+                    || !self.active_coroutines.is_empty()
+                    || self.current_thread != MAIN_THREAD;
+                let has_handler = self.handlers.last().is_some_and(|h| h.call_depth == depth);
+                if !has_caller || has_handler {
+                    return Err(Error::InvalidTailCall);
                 }
 
-                Instruction::SubFlt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::SubFlt"),
-                        line: 165,
-                    });
-
-                    let a = self.get_float(source_register1).unwrap();
-                    let b = self.get_float(source_register2).unwrap();
+                self.registers = self.read_registers(arguments);
+                self.instruction_pointer = self.label_address(label)?;
+            }
 
-                    self.registers[destination_register.as_index()] = Value::Float(a - b);
-                    self.increment_ip();
+            Instruction::Return(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Return"),
+                    line: line!(),
+                });
+
+                let value = self.register(register).clone();
+                let depth = self.call_stack.len();
+
+                if let Some(frame) = self.call_stack.pop() {
+                    // handlers the function did not remove cannot outlive it
+                    self.handlers.retain(|handler| handler.call_depth < depth);
+
+                    self.registers = frame.registers;
+                    self.set_register(frame.return_register, value);
+                    self.instruction_pointer = frame.return_address;
+                } else if let Some(register) = self.leave_coroutine(CoroutineStatus::Finished) {
+                    // returning from the body of a coroutine finishes it
+                    self.set_register(register, value);
+                } else if self.current_thread != MAIN_THREAD {
+                    // returning from the body of a spawned thread finishes it
+                    self.finish_thread(value)?;
+                } else {
+                    return Err(Error::StackUnderflow);
                 }
+            }
 
-                Instruction::MulInt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::MulInt"),
-                        line: 177,
-                    });
+            Instruction::LoadFunc(register, ref label, arity) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::LoadFunc"),
+                    line: line!(),
+                });
 
-                    let a = self.get_int(source_register1).unwrap();
-                    let b = self.get_int(source_register2).unwrap();
+                self.set_register(register, Value::Function(label.clone(), arity));
+                self.increment_ip();
+            }
 
-                    self.registers[destination_register.as_index()] = Value::Int(a * b);
-                    self.increment_ip();
-                }
+            Instruction::MakeClosure(register, ref label, arity, ref captured_registers) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::MakeClosure"),
+                    line: line!(),
+                });
 
-                Instruction::MulFlt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::MulFlt"),
-                        line: 189,
-                    });
+                let captures = self.read_registers(captured_registers);
 
-                    let a = self.get_float(source_register1).unwrap();
-                    let b = self.get_float(source_register2).unwrap();
+                self.set_register(register, Value::Closure(label.clone(), arity, captures));
+                self.increment_ip();
+            }
 
-                    self.registers[destination_register.as_index()] = Value::Float(a * b);
-                    self.increment_ip();
-                }
+            Instruction::CreateCoroutine(register, ref label, ref arguments) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::CreateCoroutine"),
+                    line: line!(),
+                });
 
-                Instruction::DivInt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::DivInt"),
-                        line: 201,
-                    });
+                let address = self.label_address(label)?;
+                let context = Context::new(address, self.read_registers(arguments));
+                let coroutine = Rc::new(RefCell::new(Coroutine::new(context)));
+                let coroutine = self.allocate(Value::Coroutine(coroutine));
 
-                    let a = self.get_int(source_register1).unwrap();
-                    let b = self.get_int(source_register2).unwrap();
+                self.set_register(register, coroutine);
+                self.increment_ip();
+            }
 
-                    if b == 0 {
-                        return Err(Error::DivisionByZero);
-                    } else {
-                        self.registers[destination_register.as_index()] = Value::Int(a / b);
+            Instruction::Resume(register, coroutine_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Resume"),
+                    line: line!(),
+                });
+
+                let coroutine = match self.register(coroutine_register) {
+                    Value::Coroutine(coroutine) => coroutine.clone(),
+                    _ => {
+                        return Err(Error::ExpectedType(
+                            String::from("coroutine"),
+                            coroutine_register,
+                        ))
                     }
+                };
 
-                    self.increment_ip();
+                if coroutine.borrow().status != CoroutineStatus::Suspended {
+                    return Err(Error::CoroutineNotSuspended);
                 }
 
-                Instruction::DivFlt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::DivFlt"),
-                        line: 218,
-                    });
+                // the resumer continues after this instruction once the coroutine yields
+                self.increment_ip();
 
-                    let a = self.get_float(source_register1).unwrap();
-                    let b = self.get_float(source_register2).unwrap();
+                {
+                    let mut coroutine = coroutine.borrow_mut();
+                    self.swap_context(&mut coroutine.context);
+                    coroutine.status = CoroutineStatus::Running;
+                }
 
-                    if b == 0.0 {
-                        return Err(Error::DivisionByZero);
-                    } else {
-                        self.registers[destination_register.as_index()] = Value::Float(a / b);
-                    }
+                self.active_coroutines.push(ActiveCoroutine {
+                    coroutine,
+                    register,
+                });
+            }
 
-                    self.increment_ip();
-                }
+            Instruction::Yield(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Yield"),
+                    line: line!(),
+                });
 
-                Instruction::ConcatStrings(
-                    destination_register,
-                    source_register1,
-                    source_register2,
-                ) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::ConcatStrings"),
-                        line: 235,
-                    });
-
-                    let a = self.get_string(source_register1).unwrap();
-                    let b = self.get_string(source_register2).unwrap();
-                    let result = Value::String(format!("{a}{b}"));
-
-                    self.registers[destination_register.as_index()] = result;
-                    self.increment_ip();
+                if self.active_coroutines.is_empty() {
+                    return Err(Error::YieldOutsideCoroutine);
                 }
 
-                Instruction::AndBool(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::AndBool"),
-                        line: 247,
-                    });
+                let value = self.register(register).clone();
+                self.increment_ip();
 
-                    let a = self.get_bool(source_register1).unwrap();
-                    let b = self.get_bool(source_register2).unwrap();
-
-                    self.registers[destination_register.as_index()] = Value::Bool(a && b);
-                    self.increment_ip();
+                if let Some(register) = self.leave_coroutine(CoroutineStatus::Suspended) {
+                    self.set_register(register, value);
                 }
+            }
 
-                Instruction::OrBool(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::OrBool"),
-                        line: 259,
-                    });
+            Instruction::Spawn(register, ref label, ref arguments) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Spawn"),
+                    line: line!(),
+                });
 
-                    let a = self.get_bool(source_register1).unwrap();
-                    let b = self.get_bool(source_register2).unwrap();
+                let address = self.label_address(label)?;
+                let context = Context::new(address, self.read_registers(arguments));
 
-                    self.registers[destination_register.as_index()] = Value::Bool(a || b);
-                    self.increment_ip();
-                }
+                self.threads.push(Thread::new(context));
+                self.set_register(register, Value::Thread(self.threads.len() - 1));
+                self.increment_ip();
+            }
 
-                Instruction::LtInt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::LtInt"),
-                        line: 271,
-                    });
+            Instruction::Join(register, thread_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Join"),
+                    line: line!(),
+                });
 
-                    let a = self.get_int(source_register1).unwrap();
-                    let b = self.get_int(source_register2).unwrap();
+                let id = match self.register(thread_register) {
+                    Value::Thread(id) if *id < self.threads.len() => *id,
+                    _ => return Err(Error::ExpectedType(String::from("thread"), thread_register)),
+                };
 
-                    self.registers[destination_register.as_index()] = Value::Bool(a < b);
+                if let ThreadState::Finished(value) = &self.threads[id].state {
+                    let value = value.clone();
+
+                    self.set_register(register, value);
                     self.increment_ip();
+                } else {
+                    // the join is executed again once the thread has finished
+                    self.threads[self.current_thread].state = ThreadState::Joining(id);
+                    self.switch_thread()?;
                 }
+            }
 
-                Instruction::GtInt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::GtInt"),
-                        line: 283,
-                    });
+            Instruction::ChannelNew(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::ChannelNew"),
+                    line: line!(),
+                });
 
-                    let a = self.get_int(source_register1).unwrap();
-                    let b = self.get_int(source_register2).unwrap();
+                let channel = self.allocate(Value::Channel(Channel::default()));
 
-                    self.registers[destination_register.as_index()] = Value::Bool(a > b);
-                    self.increment_ip();
-                }
+                self.set_register(register, channel);
+                self.increment_ip();
+            }
 
-                Instruction::LtFlt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::LtFlt"),
-                        line: 295,
-                    });
+            Instruction::Send(channel_register, value_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Send"),
+                    line: line!(),
+                });
 
-                    let a = self.get_float(source_register1).unwrap();
-                    let b = self.get_float(source_register2).unwrap();
+                let channel = self.get_channel(channel_register)?;
+                let value = self.register(value_register).clone();
 
-                    self.registers[destination_register.as_index()] = Value::Bool(a < b);
-                    self.increment_ip();
+                channel.borrow_mut().push_back(value);
+
+                // wake up the threads waiting on this channel
+                for thread in self.threads.iter_mut() {
+                    if let ThreadState::Receiving(waiting) = &thread.state {
+                        if Rc::ptr_eq(waiting, &channel) {
+                            thread.state = ThreadState::Runnable;
+                        }
+                    }
                 }
 
-                Instruction::GtFlt(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::GtFlt"),
-                        line: 307,
-                    });
+                self.increment_ip();
+            }
+
+            Instruction::Recv(register, channel_register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Recv"),
+                    line: line!(),
+                });
 
-                    let a = self.get_float(source_register1).unwrap();
-                    let b = self.get_float(source_register2).unwrap();
+                let channel = self.get_channel(channel_register)?;
+                let value = channel.borrow_mut().pop_front();
 
-                    self.registers[destination_register.as_index()] = Value::Bool(a > b);
+                if let Some(value) = value {
+                    self.set_register(register, value);
                     self.increment_ip();
+                } else {
+                    // the receive is executed again once a value has been sent
+                    self.threads[self.current_thread].state = ThreadState::Receiving(channel);
+                    self.switch_thread()?;
                 }
+            }
 
-                Instruction::EqBool(destination_register, source_register1, source_register2) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::EqBool"),
-                        line: 319,
-                    });
+            Instruction::Try(ref label, register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Try"),
+                    line: line!(),
+                });
+
+                self.handlers.push(ExceptionHandler {
+                    catch_address: self.label_address(label)?,
+                    register,
+                    call_depth: self.call_stack.len(),
+                    stack_length: self.stack.len(),
+                });
+                self.increment_ip();
+            }
 
-                    let a = self.get_bool(source_register1).unwrap();
-                    let b = self.get_bool(source_register2).unwrap();
+            Instruction::EndTry => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::EndTry"),
+                    line: line!(),
+                });
 
-                    self.registers[destination_register.as_index()] = Value::Bool(a == b);
-                    self.increment_ip();
+                if self.handlers.pop().is_none() {
+                    return Err(Error::StackUnderflow);
                 }
 
-                Instruction::Print(value_or_register) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::Print"),
-                        line: 331,
-                    });
+                self.increment_ip();
+            }
 
-                    match value_or_register {
-                        ValueOrRegister::Value(string) => print!("{}", string),
-                        ValueOrRegister::Register(register) => {
-                            let value = &self.registers[register.as_index()];
-                            print!("{}", value);
-                        }
-                    }
+            Instruction::Throw(register) => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Throw"),
+                    line: line!(),
+                });
 
-                    self.increment_ip();
-                }
+                let value = self.register(register).clone();
+                let error = Error::UncaughtException(value.to_string());
 
-                Instruction::CreateArray(register) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::CreateArray"),
-                        line: 347,
-                    });
+                self.throw(value, error)?;
+            }
 
-                    self.registers[register.as_index()] = Value::Array(Vec::new());
-                    self.increment_ip();
-                }
+            Instruction::Gc => {
+                self.frames.push(StackFrame {
+                    file_name: String::from("boltvm"),
+                    function_name: String::from("Instruction::Gc"),
+                    line: line!(),
+                });
 
-                Instruction::ArrayAdd(register, value) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::ArrayAdd"),
-                        line: 357,
-                    });
+                self.collect_garbage();
+                self.increment_ip();
+            }
 
-                    if let Value::Array(array) = &mut self.registers[register.as_index()] {
-                        array.push(value);
-                    } else {
-                        return Err(Error::ExpectedType(String::from("array"), register));
-                    }
+            Instruction::Halt => self.halted = true,
+        }
 
-                    self.increment_ip();
-                }
+        Ok(())
+    }
 
-                Instruction::GetArrayElemPtr(destination_register, array_register, index) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::GetArrayElemPtr"),
-                        line: 371,
-                    });
+    /// Reads a register of the current frame. Registers that were never written are `Null`
+    #[inline]
+    fn register(&self, register: Register) -> &Value {
+        self.registers.get(register.as_index()).unwrap_or(&Value::Null)
+    }
 
-                    if let Value::Array(array) = &self.registers[array_register.as_index()] {
-                        if let Some(value) = array.get(index) {
-                            self.registers[destination_register.as_index()] = value.clone();
-                        } else {
-                            return Err(Error::ArrayIndexOutOfBounds(index));
-                        }
-                    } else {
-                        return Err(Error::ExpectedType(String::from("array"), array_register));
-                    }
+    /// Mutably borrows a register of the current frame, growing the register file if needed
+    #[inline]
+    fn register_mut(&mut self, register: Register) -> &mut Value {
+        let index = register.as_index();
+        if index >= self.registers.len() {
+            self.registers.resize(index + 1, Value::Null);
+        }
 
-                    self.increment_ip();
-                }
+        &mut self.registers[index]
+    }
 
-                Instruction::GetArrayLength(array_register, destination_register) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::GetArrayLength"),
-                        line: 396,
-                    });
+    #[inline]
+    fn set_register(&mut self, register: Register, value: Value) {
+        *self.register_mut(register) = value;
+    }
 
-                    if let Value::Array(array) = &self.registers[array_register.as_index()] {
-                        let length = array.len();
-                        self.registers[destination_register.as_index()] = Value::Int(length as i32);
-                    } else {
-                        return Err(Error::ExpectedType(String::from("array"), array_register));
-                    }
+    /// Applies an integer operation to two registers and stores the result in the destination
+    /// register. The operation returns `None` if the result overflows
+    #[inline]
+    fn int_operation(
+        &mut self,
+        destination_register: Register,
+        source_register1: Register,
+        source_register2: Register,
+        operation: impl Fn(i64, i64) -> Option<i64>,
+    ) -> Result<()> {
+        let a = self.get_int(source_register1)?;
+        let b = self.get_int(source_register2)?;
+
+        match operation(a, b) {
+            Some(result) => self.set_register(destination_register, Value::Int(result)),
+            None => return Err(Error::IntegerOverflow),
+        }
 
-                    self.increment_ip();
-                }
+        Ok(())
+    }
 
-                Instruction::Push(value) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::Push"),
-                        line: 411,
-                    });
+    /// Applies an unsigned integer operation to two registers, like `int_operation`
+    #[inline]
+    fn uint_operation(
+        &mut self,
+        destination_register: Register,
+        source_register1: Register,
+        source_register2: Register,
+        operation: impl Fn(u64, u64) -> Option<u64>,
+    ) -> Result<()> {
+        let a = self.get_uint(source_register1)?;
+        let b = self.get_uint(source_register2)?;
+
+        match operation(a, b) {
+            Some(result) => self.set_register(destination_register, Value::UInt(result)),
+            None => return Err(Error::IntegerOverflow),
+        }
 
-                    self.stack.push(value);
-                    self.increment_ip();
-                }
+        Ok(())
+    }
 
-                Instruction::Pop(register) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::Pop"),
-                        line: 421,
-                    });
+    /// Applies a float function to a register and stores the result
+    #[inline]
+    fn float_function(
+        &mut self,
+        destination_register: Register,
+        source_register: Register,
+        function: impl Fn(f64) -> f64,
+    ) -> Result<()> {
+        let value = self.get_float(source_register)?;
+
+        self.set_register(destination_register, Value::Float(function(value)));
+        Ok(())
+    }
 
-                    if let Some(value) = self.stack.pop() {
-                        self.registers[register.as_index()] = value;
-                    } else {
-                        return Err(Error::StackUnderflow);
-                    }
+    /// Applies a float operation to two registers and stores the result
+    #[inline]
+    fn float_operation(
+        &mut self,
+        destination_register: Register,
+        source_register1: Register,
+        source_register2: Register,
+        operation: impl Fn(f64, f64) -> f64,
+    ) -> Result<()> {
+        let a = self.get_float(source_register1)?;
+        let b = self.get_float(source_register2)?;
+
+        self.set_register(destination_register, Value::Float(operation(a, b)));
+        Ok(())
+    }
 
-                    self.increment_ip();
-                }
+    /// Checks that an index into an array of the given length is below the limit,
+    /// which is the length itself or one past it when the end may be referred to
+    #[inline]
+    fn check_array_index(index: i64, limit: usize, length: usize) -> Result<usize> {
+        match usize::try_from(index) {
+            Ok(index) if index < limit => Ok(index),
+            _ => Err(Error::ArrayIndexOutOfBounds(index, length)),
+        }
+    }
 
-                Instruction::CopyReg(destination_register, source_register) => {
-                    self.frames.push(StackFrame {
-                        file_name: String::from("boltvm"),
-                        function_name: String::from("Instruction::CopyReg"),
-                        line: 484,
-                    });
+    /// Gets the number of bits to shift by, which must be less than the width of an integer
+    #[inline]
+    fn get_shift_amount(&self, register: Register) -> Result<u32> {
+        let amount = self.get_int(register)?;
 
-                    let source = self.registers[source_register.as_index()].clone();
+        match u32::try_from(amount) {
+            Ok(amount) if amount < i64::BITS => Ok(amount),
+            _ => Err(Error::InvalidShift(amount)),
+        }
+    }
 
-                    self.registers[destination_register.as_index()] = source;
-                    self.increment_ip();
-                }
+    /// Clones the values of several registers, e.g. to pass them as arguments
+    #[inline]
+    fn read_registers(&self, registers: &[Register]) -> Vec<Value> {
+        registers
+            .iter()
+            .map(|register| self.register(*register).clone())
+            .collect()
+    }
 
-                Instruction::Halt => break,
-            }
+    /// Compares two registers for `Eq` and `Ne`. Null is unequal to every other value,
+    /// but comparing two values of different types is an error
+    fn values_equal(&self, register1: Register, register2: Register) -> Result<bool> {
+        let a = self.register(register1);
+        let b = self.register(register2);
+
+        if a.type_name() != b.type_name() && !a.is_null() && !b.is_null() {
+            return Err(Error::TypeMismatch(
+                a.type_name().to_string(),
+                b.type_name().to_string(),
+            ));
         }
 
-        Ok(())
+        Ok(a.equals(b))
+    }
+
+    /// Orders two registers for `Le`, `Ge` and `Cmp`, returning `None` when a NaN is involved
+    fn compare_values(&self, register1: Register, register2: Register) -> Result<Option<Ordering>> {
+        self.register(register1).compare(self.register(register2))
     }
 
     #[inline]
-    fn get_int(&self, register: Register) -> Result<i32> {
-        match self.registers[register.as_index()] {
+    fn get_int(&self, register: Register) -> Result<i64> {
+        match *self.register(register) {
             Value::Int(value) => Ok(value),
             _ => Err(Error::ExpectedType(String::from("int"), register)),
         }
     }
 
     #[inline]
-    fn get_float(&self, register: Register) -> Result<f32> {
-        match self.registers[register.as_index()] {
+    fn get_uint(&self, register: Register) -> Result<u64> {
+        match *self.register(register) {
+            Value::UInt(value) => Ok(value),
+            _ => Err(Error::ExpectedType(String::from("uint"), register)),
+        }
+    }
+
+    #[inline]
+    fn get_float(&self, register: Register) -> Result<f64> {
+        match *self.register(register) {
             Value::Float(value) => Ok(value),
             _ => Err(Error::ExpectedType(String::from("float"), register)),
         }
@@ -524,7 +3106,7 @@ impl BoltVM {
 
     #[inline]
     fn get_string(&self, register: Register) -> Result<String> {
-        match self.registers[register.as_index()] {
+        match *self.register(register) {
             Value::String(ref value) => Ok(value.clone()),
             _ => Err(Error::ExpectedType(String::from("string"), register)),
         }
@@ -532,21 +3114,99 @@ impl BoltVM {
 
     #[inline]
     fn get_bool(&self, register: Register) -> Result<bool> {
-        match self.registers[register.as_index()] {
+        match *self.register(register) {
             Value::Bool(value) => Ok(value),
             _ => Err(Error::ExpectedType(String::from("bool"), register)),
         }
     }
 
     #[inline]
-    #[allow(dead_code)]
-    fn get_array(&self, register: Register) -> Result<Vec<Value>> {
-        match &self.registers[register.as_index()] {
+    fn get_array(&self, register: Register) -> Result<Array> {
+        match self.register(register) {
             Value::Array(value) => Ok(value.clone()),
             _ => Err(Error::ExpectedType(String::from("array"), register)),
         }
     }
 
+    /// Gets the char at the index in `index_register` of the string in `string_register`
+    #[inline]
+    fn get_char(&self, string_register: Register, index_register: Register) -> Result<char> {
+        let index = self.get_int(index_register)?;
+        let string = self.get_string(string_register)?;
+
+        match usize::try_from(index).ok().and_then(|index| string.chars().nth(index)) {
+            Some(char) => Ok(char),
+            None => Err(Error::IndexOutOfBounds(index, string.chars().count())),
+        }
+    }
+
+    #[inline]
+    fn get_bytes(&self, register: Register) -> Result<&[u8]> {
+        match self.register(register) {
+            Value::Bytes(bytes) => Ok(bytes),
+            _ => Err(Error::ExpectedType(String::from("bytes"), register)),
+        }
+    }
+
+    /// Checks that `start..end` is a valid range into something of the given length
+    #[inline]
+    fn check_range(start: i64, end: i64, length: usize) -> Result<std::ops::Range<usize>> {
+        let start_index = match usize::try_from(start) {
+            Ok(start) if start <= length => start,
+            _ => return Err(Error::IndexOutOfBounds(start, length)),
+        };
+
+        match usize::try_from(end) {
+            Ok(end) if end >= start_index && end <= length => Ok(start_index..end),
+            _ => Err(Error::IndexOutOfBounds(end, length)),
+        }
+    }
+
+    #[inline]
+    fn get_map(&self, register: Register) -> Result<Map> {
+        match self.register(register) {
+            Value::Map(map) => Ok(map.clone()),
+            _ => Err(Error::ExpectedType(String::from("map"), register)),
+        }
+    }
+
+    #[inline]
+    fn get_map_key(&self, register: Register) -> Result<MapKey> {
+        match self.register(register).as_map_key() {
+            Some(key) => Ok(key),
+            None => Err(Error::ExpectedType(String::from("map key"), register)),
+        }
+    }
+
+    /// Checks that the register holds an instance of the field's struct type,
+    /// and returns the resolved index of the field
+    #[inline]
+    fn field_index(&self, register: Register, field: &Field) -> Result<usize> {
+        match self.register(register) {
+            Value::Struct(struct_type, _) if struct_type.name == field.struct_name => {}
+            _ => {
+                let expected_type = format!("struct {}", field.struct_name);
+                return Err(Error::ExpectedType(expected_type, register));
+            }
+        }
+
+        match field.index {
+            Some(index) => Ok(index),
+            None => Err(Error::FieldNotDefined(
+                field.struct_name.clone(),
+                field.field_name.clone(),
+            )),
+        }
+    }
+
+    #[inline]
+    fn get_channel(&self, register: Register) -> Result<Channel> {
+        match self.register(register) {
+            Value::Channel(channel) => Ok(channel.clone()),
+            _ => Err(Error::ExpectedType(String::from("channel"), register)),
+        }
+    }
+
     pub fn debug_dump(&self) {
         println!("========== debug dump ==========");
 
@@ -591,7 +3251,272 @@ impl BoltVM {
             println!("	{}stack is empty{}\n", Color::Red, Color::Reset);
         }
 
-		println!("number of instructions executed: {}{}{}", Color::Blue, self.instruction_pointer, Color::Reset);
+		println!("number of instructions executed: {}{}{}", Color::Blue, self.executed_instructions, Color::Reset);
         println!("======= end of debug dump ======");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::BoltVM;
+    use crate::error::Error;
+    use crate::instruction::Instruction::{self, *};
+    use crate::register::Register;
+    use crate::types;
+    use crate::value::Value;
+
+    fn run(program: Vec<Instruction>) -> BoltVM {
+        let mut vm = BoltVM::new();
+
+        vm.execute(program).expect("program should run");
+        vm
+    }
+
+    fn label(name: &str) -> types::Label {
+        types::Label::new(name)
+    }
+
+    #[test]
+    fn labels_are_resolved_before_execution() {
+        let vm = run(vec![
+            LoadInt(Register(0), 0),
+            LoadInt(Register(1), 1),
+            LoadInt(Register(2), 3),
+            Label(label("loop")),
+            AddInt(Register(0), Register(0), Register(1)),
+            LtInt(Register(3), Register(0), Register(2)),
+            JumpIfTrue(Register(3), label("loop")),
+        ]);
+
+        assert!(matches!(vm.registers[0], Value::Int(3)));
+        assert!(matches!(
+            vm.program[6],
+            JumpIfTrue(_, types::Label { address: Some(3), .. })
+        ));
+    }
+
+    #[test]
+    fn switch_dispatches_through_resolved_table() {
+        let mut program = vec![
+            LoadInt(Register(0), 700),
+            Switch(
+                Register(0),
+                (0..1000).map(|i| label(&format!("case{i}"))).collect(),
+                label("default"),
+            ),
+        ];
+        for i in 0..1000 {
+            program.push(Label(label(&format!("case{i}"))));
+            program.push(LoadInt(Register(1), i));
+            program.push(Halt);
+        }
+        program.push(Label(label("default")));
+        program.push(LoadInt(Register(1), -1));
+
+        let vm = run(program.clone());
+        assert!(matches!(vm.registers[1], Value::Int(700)));
+
+        let Switch(_, labels, default) = &vm.program[1] else {
+            panic!("expected a switch");
+        };
+        assert!(labels.iter().chain([default]).all(|label| label.address.is_some()));
+
+        program[0] = LoadInt(Register(0), 1000);
+        let vm = run(program);
+        assert!(matches!(vm.registers[1], Value::Int(-1)));
+    }
+
+    #[test]
+    fn coroutine_yields_and_returns_to_its_resumer() {
+        let vm = run(vec![
+            CreateCoroutine(Register(0), label("body"), vec![]),
+            Resume(Register(1), Register(0)),
+            Resume(Register(2), Register(0)),
+            Resume(Register(3), Register(0)),
+            Halt,
+            Label(label("body")),
+            LoadInt(Register(0), 1),
+            Yield(Register(0)),
+            LoadInt(Register(0), 2),
+            Yield(Register(0)),
+            LoadInt(Register(0), 3),
+            Return(Register(0)),
+        ]);
+
+        assert!(matches!(vm.registers[1], Value::Int(1)));
+        assert!(matches!(vm.registers[2], Value::Int(2)));
+        assert!(matches!(vm.registers[3], Value::Int(3)));
+    }
+
+    #[test]
+    fn coroutine_running_off_the_end_finishes_it() {
+        let vm = run(vec![
+            CreateCoroutine(Register(0), label("body"), vec![]),
+            Resume(Register(1), Register(0)),
+            Resume(Register(1), Register(0)),
+            LoadInt(Register(9), 1),
+            Try(label("catch"), Register(2)),
+            Resume(Register(3), Register(0)),
+            Halt,
+            Label(label("catch")),
+            Halt,
+            Label(label("body")),
+            Yield(Register(0)),
+        ]);
+
+        assert!(matches!(vm.registers[1], Value::Null));
+        assert!(matches!(vm.registers[9], Value::Int(1)));
+        assert!(matches!(&vm.registers[2], Value::String(message) if message.contains("finished")));
+    }
+
+    #[test]
+    fn function_running_off_the_end_is_an_error() {
+        let mut vm = BoltVM::new();
+        let result = vm.execute(vec![
+            Call(label("function"), vec![], Register(0)),
+            Halt,
+            Label(label("function")),
+            LoadInt(Register(0), 1),
+        ]);
+
+        assert!(matches!(result, Err(Error::MissingReturn)));
+
+        let vm = run(vec![
+            Try(label("catch"), Register(1)),
+            Call(label("function"), vec![], Register(0)),
+            Halt,
+            Label(label("catch")),
+            LoadInt(Register(2), 1),
+            Halt,
+            Label(label("function")),
+            LoadInt(Register(0), 1),
+        ]);
+
+        assert!(matches!(vm.registers[2], Value::Int(1)));
+    }
+
+    #[test]
+    fn joined_thread_hands_back_its_result() {
+        let vm = run(vec![
+            LoadInt(Register(1), 20),
+            Spawn(Register(0), label("thread"), vec![Register(1)]),
+            Join(Register(2), Register(0)),
+            Halt,
+            Label(label("thread")),
+            LoadInt(Register(1), 1),
+            AddInt(Register(0), Register(0), Register(1)),
+            Return(Register(0)),
+        ]);
+
+        assert!(matches!(vm.registers[2], Value::Int(21)));
+    }
+
+    #[test]
+    fn halt_in_spawned_thread_leaves_main_context_loaded() {
+        let vm = run(vec![
+            LoadInt(Register(9), 1),
+            Spawn(Register(0), label("thread"), vec![]),
+            Join(Register(1), Register(0)),
+            Halt,
+            Label(label("thread")),
+            Halt,
+        ]);
+
+        assert!(matches!(vm.registers[9], Value::Int(1)));
+    }
+
+    #[test]
+    fn uncaught_error_in_spawned_thread_leaves_main_context_loaded() {
+        let mut vm = BoltVM::new();
+        let result = vm.execute(vec![
+            LoadInt(Register(9), 1),
+            Spawn(Register(0), label("thread"), vec![]),
+            Join(Register(1), Register(0)),
+            Halt,
+            Label(label("thread")),
+            LoadInt(Register(0), 1),
+            LoadInt(Register(1), 0),
+            DivInt(Register(0), Register(0), Register(1)),
+            Return(Register(0)),
+        ]);
+
+        assert!(matches!(result, Err(Error::DivisionByZero)));
+        assert!(matches!(vm.registers[9], Value::Int(1)));
+    }
+
+    #[test]
+    fn busy_main_thread_is_preempted() {
+        let mut vm = BoltVM::new();
+        vm.time_slice = 4;
+        vm.execute(vec![
+            LoadInt(Register(9), 1),
+            Spawn(Register(0), label("thread"), vec![]),
+            Label(label("spin")),
+            Jump(label("spin")),
+            Label(label("thread")),
+            Halt,
+        ])
+        .expect("program failed");
+
+        assert!(matches!(vm.registers[9], Value::Int(1)));
+    }
+
+    #[test]
+    fn receiving_on_an_empty_channel_deadlocks() {
+        let mut vm = BoltVM::new();
+        let result = vm.execute(vec![
+            ChannelNew(Register(0)),
+            Recv(Register(1), Register(0)),
+            Halt,
+        ]);
+
+        assert!(matches!(result, Err(Error::Deadlock(_))));
+    }
+
+    #[test]
+    fn signedness_conversions_are_checked() {
+        let vm = run(vec![
+            LoadInt(Register(0), 42),
+            IntToUInt(Register(1), Register(0)),
+            UIntToInt(Register(2), Register(1)),
+            Halt,
+        ]);
+
+        assert!(matches!(vm.registers[1], Value::UInt(42)));
+        assert!(matches!(vm.registers[2], Value::Int(42)));
+
+        let mut vm = BoltVM::new();
+        let result = vm.execute(vec![
+            LoadInt(Register(0), -1),
+            IntToUInt(Register(1), Register(0)),
+            Halt,
+        ]);
+
+        assert!(matches!(result, Err(Error::IntegerOverflow)));
+
+        let mut vm = BoltVM::new();
+        let result = vm.execute(vec![
+            LoadUInt(Register(0), u64::MAX),
+            UIntToInt(Register(1), Register(0)),
+            Halt,
+        ]);
+
+        assert!(matches!(result, Err(Error::IntegerOverflow)));
+    }
+
+    #[test]
+    fn wrapping_and_saturating_negation_and_modulo() {
+        let vm = run(vec![
+            LoadInt(Register(0), i64::MIN),
+            LoadInt(Register(1), -1),
+            ModIntWrap(Register(2), Register(0), Register(1)),
+            NegIntWrap(Register(3), Register(0)),
+            NegIntSat(Register(4), Register(0)),
+            Halt,
+        ]);
+
+        assert!(matches!(vm.registers[2], Value::Int(0)));
+        assert!(matches!(vm.registers[3], Value::Int(i64::MIN)));
+        assert!(matches!(vm.registers[4], Value::Int(i64::MAX)));
+    }
+}